@@ -0,0 +1,58 @@
+//! The `nedots` CLI, run by `main.rs`.
+
+use crate::{errors, models::report, ExecuteWith, RootCmd};
+use clap::Parser;
+use report::Output;
+use std::process::ExitCode;
+
+fn init() -> RootCmd {
+    let root_cmd = RootCmd::parse();
+
+    // Set logging level to the given verbosity, but if it's set lower than
+    // error, then bump it up to info - unless user has explicitly asked for
+    // silence, in that case we'll leave it be.
+    let mut verbosity = root_cmd.verbose.log_level_filter();
+    if root_cmd
+        .verbose
+        .log_level_filter()
+        .le(&log::LevelFilter::Error)
+        && !root_cmd.verbose.is_silent()
+    {
+        verbosity = log::LevelFilter::Info;
+    }
+
+    // Initialize our logger
+    if cfg!(debug_assertions) {
+        // If debugging, leave timestamp & target
+        env_logger::Builder::new().filter_level(verbosity).init();
+    } else {
+        // User will see this output, so we'll make it a little more friendly
+        env_logger::Builder::new()
+            .format_timestamp(None)
+            .format_target(false)
+            .filter_level(verbosity)
+            .init();
+    }
+
+    log::debug!("{:#?}", root_cmd);
+    root_cmd
+}
+
+fn run(root_cmd: RootCmd) -> ExitCode {
+    let result = root_cmd.exec_with(&root_cmd);
+    if root_cmd.output() == Output::Json {
+        report::print_json(&result);
+    }
+
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            log::error!("❌ {}", err);
+            ExitCode::from(errors::exit_code(&err))
+        }
+    }
+}
+
+pub fn main() -> ExitCode {
+    run(init())
+}
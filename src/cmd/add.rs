@@ -0,0 +1,152 @@
+//! Command to add files, directories or git repositories to `nedots.yml`.
+//!
+//! YAML & TOML config files are edited in place, so comments & ordering
+//! are preserved.
+
+use crate::{
+    models::{
+        config::Config,
+        format::Format,
+        git_repo::GitRepo,
+        hooks::Event,
+        report::{Action, Report},
+    },
+    utils::paths::{expand_path, ResolvePath},
+};
+use anyhow::Context;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct AddCmd {
+    /// Files, directories or git repositories to add. Git repositories are
+    /// added to `git_repos`, everything else to `sources`
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
+
+impl super::RunWith<Config> for AddCmd {
+    /// Append each of `paths` to `sources` or `git_repos`, then gather new
+    /// `sources`, between the gather hooks. `config.file` is left alone if
+    /// nothing was added.
+    ///
+    /// * `config`: &Config
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let mut report = Report::default();
+        let mut raw = std::fs::read_to_string(&config.file)
+            .with_context(|| format!("Failed to read `{}`", config.file.display()))?;
+
+        let format = Format::from_path(&config.file);
+        let mut gather = Vec::new();
+        for path in &self.paths {
+            let path = path.resolve_path()?;
+
+            if path.join(".git").exists() {
+                if config.git_repos.iter().any(|repo| repo.path == path) {
+                    log::warn!("`{}` is already managed", path.display());
+                    continue;
+                }
+
+                let remote = GitRepo::new("", &path).origin_url()?;
+                let repo = GitRepo::new(&remote, &path.strip_home());
+                raw = format.append_to_list(&raw, "git_repos", &repo)?;
+            } else {
+                if config
+                    .sources
+                    .iter()
+                    .any(|source| expand_path(source).is_ok_and(|s| s == path))
+                {
+                    log::warn!("`{}` is already managed", path.display());
+                    continue;
+                }
+
+                raw = format.append_to_list(&raw, "sources", &path.strip_home())?;
+                gather.push(path.strip_home());
+            }
+
+            log::info!(
+                "➕ Added `{}`",
+                console::style(path.display()).green().bold()
+            );
+            report.push(Action::Added { path });
+        }
+
+        if report.actions.is_empty() {
+            log::info!("Nothing to add");
+            return Ok(report);
+        }
+
+        log::trace!("Writing `{}`...", config.file.display());
+        std::fs::write(&config.file, raw)?;
+
+        if !gather.is_empty() {
+            // The same as `sync --gather` would, so `ignore` & hooks apply.
+            config.hooks.around(Event::Gather, &config.hook_env(), || {
+                crate::ops::gather_sources(config, &gather, &mut report, false)
+            })?;
+            report.check("sources")?;
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AddCmd;
+    use crate::{cmd::RunWith, models::config};
+
+    #[test]
+    fn added_sources_are_gathered_without_ignored_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (src, nedots) = (tmp.path().join("app"), tmp.path().join("nedots.yml"));
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(src.join("app.conf"), "a").unwrap();
+        std::fs::write(src.join("app.conf.swp"), "b").unwrap();
+        std::fs::write(
+            &nedots,
+            format!(
+                "remote: x\ndots_dir: {0}/dots\nignore: ['*.swp']\nsources: []\n\
+                 hooks:\n  post_gather: touch {0}/gathered\n",
+                tmp.path().display()
+            ),
+        )
+        .unwrap();
+
+        let config = config::read(&nedots).unwrap();
+        AddCmd {
+            paths: vec![src.clone()],
+        }
+        .run_with(&config)
+        .unwrap();
+
+        let dst = config.dots_path(&src).unwrap();
+        assert!(dst.join("app.conf").exists());
+        assert!(!dst.join("app.conf.swp").exists());
+        assert!(tmp.path().join("gathered").exists());
+    }
+
+    #[test]
+    fn config_is_untouched_when_nothing_is_added() {
+        let tmp = tempfile::tempdir().unwrap();
+        let nedots = tmp.path().join("nedots.yml");
+        let raw = format!(
+            "remote: x\n# Managed\nsources: [{0}]\ndots_dir: {0}/dots\n",
+            tmp.path().display()
+        );
+        std::fs::write(&nedots, &raw).unwrap();
+        let before = std::fs::metadata(&nedots).unwrap().modified().unwrap();
+
+        let report = AddCmd {
+            paths: vec![tmp.path().into()],
+        }
+        .run_with(&config::read(&nedots).unwrap())
+        .unwrap();
+
+        assert!(report.actions.is_empty());
+        assert_eq!(std::fs::read_to_string(&nedots).unwrap(), raw);
+        assert_eq!(
+            std::fs::metadata(&nedots).unwrap().modified().unwrap(),
+            before
+        );
+    }
+}
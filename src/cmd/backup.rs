@@ -1,39 +1,38 @@
-use crate::{models::config::Config, utils::paths::MakeDirs};
-use std::path::{Path, PathBuf};
+use crate::{
+    models::{
+        config::Config,
+        report::{Action, Report},
+    },
+    Nedots,
+};
 
 #[derive(Debug, clap::Args)]
-pub struct BackupCmd;
+pub struct BackupCmd {
+    /// Stop at the first source that fails to back up
+    #[arg(long)]
+    fail_fast: bool,
+}
 
 impl super::RunWith<Config> for BackupCmd {
     /// Backup `sources` to `backup_dir/{timestamp}`.
     ///
     /// * `config`: &Config
-    fn run_with(&self, config: &Config) -> anyhow::Result<()> {
-        let dst = &config.backup_dir.join(crate::utils::get_timestamp());
-        backup(&config.sources, dst)?;
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let report = Nedots::prepared(config.clone(), self.fail_fast).backup()?;
 
-        log::info!(
-            "💽 {} {}",
-            console::style("All backed up!").bold(),
-            console::style(dst.display()).blue()
-        );
-        Ok(())
-    }
-}
+        let backed_up = report.actions.iter().find_map(|action| match action {
+            Action::BackedUp { path } => Some(path),
+            _ => None,
+        });
+        match backed_up {
+            Some(dst) => log::info!(
+                "💽 {} {}",
+                console::style("All backed up!").bold(),
+                console::style(dst.display()).blue()
+            ),
+            None => log::info!("💽 {}", console::style("Nothing to back up").bold()),
+        }
 
-/// Make directory, `backup_dir/{timestamp}` and loop through `sources`. Copy
-/// each to `dst`.
-///
-/// * `sources`: &[PathBuf]
-/// * `to`: &Path
-pub fn backup(sources: &[PathBuf], to: &Path) -> anyhow::Result<()> {
-    log::trace!("Backing up to `{}`", to.display().to_string());
-
-    to.make_all_dirs()?;
-    for source in sources {
-        let dst = crate::utils::join_paths(to, source);
-        crate::ops::copy(source, &dst)?;
+        Ok(report)
     }
-
-    Ok(())
 }
@@ -0,0 +1,296 @@
+//! Command to take a fresh machine from nothing to installed in one go.
+//!
+//! The root repository is cloned, & `nedots.yml` is read from it rather than
+//! from $XDG_CONFIG_HOME. If there's a profile for this host, a local
+//! `nedots.yml` that includes both is written, so that other commands layer
+//! them too. Existing files are backed up before everything is installed, & a
+//! file that fails to back up is left as it is. Failures don't stop the rest
+//! from being installed.
+
+use super::init::CloneArgs;
+use crate::{
+    errors::Error,
+    models::{
+        config,
+        config::Config,
+        format::Format,
+        hooks::Event,
+        report::{Action, Report},
+    },
+    ops,
+    utils::paths::{expand_path, MakeDirs},
+    RootCmd,
+};
+use anyhow::Context;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct BootstrapCmd {
+    #[command(flatten)]
+    clone: CloneArgs,
+
+    /// Host profile to layer on top of `nedots.yml`, from `hosts` in the root
+    /// repository [default: hostname]
+    #[arg(long)]
+    profile: Option<String>,
+}
+
+impl super::RunWith<RootCmd> for BootstrapCmd {
+    /// `BootstrapCmd` can't be initialized with a `Config`, since it's in the
+    /// root repository, which doesn't exist yet.
+    ///
+    /// * `root_args`: &RootCmd
+    fn run_with(&self, root_args: &RootCmd) -> anyhow::Result<Report> {
+        let mut config = Config {
+            file: super::user_config_path(root_args),
+            ..Default::default()
+        };
+        root_args.set_dirs(&mut config)?;
+
+        let mut report = Report::default();
+        if self.clone.clone_into(&config.root)? {
+            report.push(Action::Cloned {
+                remote: self.clone.remote.clone(),
+                path: config.root.clone(),
+            });
+        }
+
+        let mut config = config::read(&self.config_file(&config, root_args)?)?;
+        root_args.set_dirs(&mut config)?;
+        config.backup_dir.make_all_dirs()?;
+        let config = config.resolve_dirs();
+        log::debug!("Bootstrapping with {:#?}", config);
+
+        // A source that failed to back up isn't overwritten, it needs attention.
+        let not_backed_up = backup(&config, &mut report)?;
+
+        let env = config.hook_env();
+        config.hooks.around(Event::Install, &env, || {
+            for source in &config.sources {
+                let dots = config.dots_path(source)?;
+                let result = match (not_backed_up.contains(source), dots.exists()) {
+                    (true, _) => Err(anyhow::anyhow!("Not installed, it failed to back up")),
+                    // Unlike `install`, a source missing from `dots` needs attention.
+                    (false, false) => Err(Error::NotFound(dots.display().to_string()).into()),
+                    (false, true) => ops::install_source(&config, source),
+                };
+                report.record(source, result, false)?;
+            }
+
+            for repo in &config.git_repos {
+                let result = ops::install_repo(&config, repo);
+                report.record(&repo.path, result, false)?;
+            }
+
+            Ok(report.is_changed())
+        })?;
+
+        report
+            .check("sources & repositories")
+            .context("Fix them & run `nedots install`")?;
+
+        log::info!("✅ {}", console::style("Bootstrapped!").bold());
+        Ok(report)
+    }
+}
+
+impl BootstrapCmd {
+    /// Which `nedots.yml` to read. The user's if it exists, otherwise the one
+    /// in the root repository, unless there's a profile for this host, in
+    /// which case the user's is written to include both.
+    ///
+    /// * `config`: &Config, `file` is the user's & `root` is resolved.
+    /// * `root_args`: &RootCmd
+    fn config_file(&self, config: &Config, root_args: &RootCmd) -> anyhow::Result<PathBuf> {
+        if config.file.exists() && !root_args.repo_config {
+            log::info!(
+                "🗒️ Using existing config @ {}",
+                console::style(config.file.display()).bold()
+            );
+            return Ok(config.file.clone());
+        }
+
+        let Some(repo_config) = Format::find(&config.root.join(config::REPO_CONFIG_FILE)) else {
+            anyhow::bail!(
+                "`{}` isn't in `{}`, commit one or run `nedots init` instead",
+                config::REPO_CONFIG_FILE,
+                config.root.display()
+            );
+        };
+
+        let host_profile = self.host_profile(config)?;
+        match host_profile {
+            Some(profile) if !config.file.exists() => {
+                let include = [repo_config, profile].map(|p| p.display().to_string());
+                super::init::init_config(
+                    config,
+                    Config {
+                        include: include.to_vec(),
+                        ..Default::default()
+                    },
+                )?;
+                Ok(config.file.clone())
+            }
+            Some(_) => {
+                log::warn!(
+                    "⚠️ `{}` exists, so the host profile isn't included",
+                    config.file.display()
+                );
+                Ok(repo_config)
+            }
+            None => {
+                log::info!(
+                    "🗒️ Using config committed @ {}",
+                    console::style(repo_config.display()).bold()
+                );
+                Ok(repo_config)
+            }
+        }
+    }
+
+    /// Profile of this host, `--profile` or the hostname, in `hosts` of the
+    /// root repository, if it exists. A missing `--profile` is an error.
+    ///
+    /// * `config`: &Config, `root` is expected to be resolved.
+    fn host_profile(&self, config: &Config) -> anyhow::Result<Option<PathBuf>> {
+        let profile = match &self.profile {
+            Some(profile) => profile.clone(),
+            None => crate::utils::hostname()?,
+        };
+        let host_file = config
+            .root
+            .join(config::HOSTS_DIR)
+            .join(format!("{}.yml", profile));
+
+        match Format::find(&host_file) {
+            Some(path) => {
+                log::info!("🖥️ Using host profile {}", console::style(&profile).bold());
+                Ok(Some(path))
+            }
+            None if self.profile.is_some() => Err(Error::NotFound(host_file.display().to_string()))
+                .with_context(|| format!("No host profile `{}`", profile)),
+            None => {
+                log::debug!("No host profile for `{}`", profile);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Backup `sources` that already exist, before they're overwritten. Every
+/// source is attempted, see `Report::record`. Returns the sources that failed
+/// to back up.
+///
+/// * `config`: &Config
+/// * `report`: &mut Report
+fn backup(config: &Config, report: &mut Report) -> anyhow::Result<Vec<PathBuf>> {
+    let existing: Vec<PathBuf> = config
+        .sources
+        .iter()
+        .filter(|source| expand_path(source).is_ok_and(|path| path.exists()))
+        .cloned()
+        .collect();
+
+    if existing.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let dst = config.backup_dir.join(crate::utils::get_timestamp());
+    let env = config.hook_env();
+    let config = Config {
+        sources: existing,
+        ..Config::clone(config)
+    };
+
+    let before = report.actions.len();
+    config.hooks.around(Event::Backup, &env, || {
+        ops::backup(&config, &dst, report, false)
+    })?;
+
+    let failed: Vec<PathBuf> = report.actions[before..]
+        .iter()
+        .filter_map(|action| match action {
+            Action::Failed { path, error } => {
+                log::error!("❌ Failed to back up `{}` ({})", path.display(), error);
+                Some(path.clone())
+            }
+            _ => None,
+        })
+        .collect();
+
+    log::info!(
+        "💽 {} {}",
+        console::style("Backed up existing files to").bold(),
+        console::style(dst.display()).blue()
+    );
+    report.push(Action::BackedUp { path: dst });
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BootstrapCmd;
+    use crate::{utils::testing::bare_repo, RootCmd, RunWith};
+    use clap::{FromArgMatches, Parser};
+
+    #[test]
+    fn bootstraps_with_host_profile() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let (shared, host) = (dir.join("etc/shared.conf"), dir.join("etc/host.conf"));
+        let dots = |path: &std::path::Path| format!("dots/ROOT{}", path.display());
+
+        let remote = dir.join("dots.git");
+        let repo_config = format!(
+            "remote: {}\nsources:\n  - {}\n",
+            remote.display(),
+            shared.display()
+        );
+        let host_config = format!("sources:\n  - {}\n", host.display());
+        bare_repo(
+            dir,
+            "dots",
+            &[
+                ("nedots.yml", &repo_config),
+                ("hosts/laptop.yml", &host_config),
+                (&dots(&shared), "shared = new"),
+                (&dots(&host), "host = new"),
+            ],
+        );
+
+        // Existing files are backed up, then overwritten.
+        std::fs::create_dir_all(dir.join("etc")).unwrap();
+        std::fs::write(&shared, "shared = old").unwrap();
+
+        let (root, user) = (dir.join("root"), dir.join("config/nedots.yml"));
+        let root_args = RootCmd::parse_from([
+            "nedots",
+            "--root",
+            &root.display().to_string(),
+            "-c",
+            &user.display().to_string(),
+        ]);
+        let cmd = clap::Command::new("bootstrap");
+        let matches = <BootstrapCmd as clap::Args>::augment_args(cmd).get_matches_from([
+            "bootstrap",
+            "--profile",
+            "laptop",
+            &remote.display().to_string(),
+        ]);
+        let bootstrap = BootstrapCmd::from_arg_matches(&matches).unwrap();
+
+        let report = bootstrap.run_with(&root_args).unwrap();
+        assert!(report.is_changed());
+        assert_eq!(std::fs::read_to_string(&shared).unwrap(), "shared = new");
+        assert_eq!(std::fs::read_to_string(&host).unwrap(), "host = new");
+
+        let backups: Vec<_> = root.join("backups").read_dir().unwrap().collect();
+        assert_eq!(backups.len(), 1);
+        let backup = crate::utils::join_paths(&backups[0].as_ref().unwrap().path(), &shared);
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), "shared = old");
+
+        // The profile is layered by other commands too.
+        let config = std::fs::read_to_string(&user).unwrap();
+        assert!(config.contains("hosts/laptop.yml"), "{}", config);
+    }
+}
@@ -3,7 +3,14 @@
 //! Useful in the event that we need a clean slate. This command can serve to
 //! perform any messy or complicated clean up operations.
 
-use crate::{models::config::Config, utils::paths::MakeDirs};
+use crate::{
+    errors::Error,
+    models::{
+        config::Config,
+        report::{Action, Report},
+    },
+    utils::paths::MakeDirs,
+};
 use std::path::Path;
 
 #[derive(Debug, clap::Args)]
@@ -25,7 +32,8 @@ impl super::RunWith<Config> for CleanCmd {
     /// Remove dots & backup directories.
     ///
     /// * `config`: &Config
-    fn run_with(&self, config: &Config) -> anyhow::Result<()> {
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let mut report = Report::default();
         if self.dots {
             confirm_clean(
                 &format!(
@@ -34,6 +42,7 @@ impl super::RunWith<Config> for CleanCmd {
                 ),
                 &config.dots_dir,
                 self.assumeyes,
+                &mut report,
             )?
         }
 
@@ -45,10 +54,11 @@ impl super::RunWith<Config> for CleanCmd {
                 ),
                 &config.backup_dir,
                 self.assumeyes,
+                &mut report,
             )?
         }
 
-        Ok(())
+        Ok(report)
     }
 }
 
@@ -57,11 +67,24 @@ impl super::RunWith<Config> for CleanCmd {
 /// * `prompt`: &str, msg to display to the user.
 /// * `path`: &Path, path of directory to remove.
 /// * `assumeyes`: bool, if given as true, we assume user will say yes to prompt.
-fn confirm_clean(prompt: &str, path: &Path, assumeyes: bool) -> anyhow::Result<()> {
-    match assumeyes {
-        true => clean(path),
-        false => confirm(prompt, clean, path),
+/// * `report`: &mut Report
+fn confirm_clean(
+    prompt: &str,
+    path: &Path,
+    assumeyes: bool,
+    report: &mut Report,
+) -> anyhow::Result<()> {
+    let cleaned = match assumeyes {
+        true => clean(path).map(|_| true)?,
+        false => confirm(prompt, clean, path)?,
+    };
+
+    if cleaned {
+        report.push(Action::Cleaned {
+            path: path.to_path_buf(),
+        });
     }
+    Ok(())
 }
 
 /// Let the user confirm their choice by presenting a `dialoger::Confirm`.
@@ -69,20 +92,22 @@ fn confirm_clean(prompt: &str, path: &Path, assumeyes: bool) -> anyhow::Result<(
 /// * `prompt`: `&str`, msg to display to the user.
 /// * `func`: `fn`, function to run if user gives an affirmative response.
 /// * `path`: `&Path`, path to run function on.
+///
+/// Returns whether `func` was run.
 fn confirm(
     prompt: &str,
     func: impl Fn(&Path) -> anyhow::Result<()>,
     path: &Path,
-) -> anyhow::Result<()> {
-    if dialoguer::Confirm::new()
+) -> anyhow::Result<bool> {
+    let confirmed = dialoguer::Confirm::new()
         .with_prompt(prompt)
         .interact()
-        .is_ok()
-    {
-        return func(path);
-    }
+        .map_err(|err| Error::Aborted(err.to_string()))?;
 
-    Ok(())
+    if confirmed {
+        func(path)?;
+    }
+    Ok(confirmed)
 }
 
 /// Remove this directory.
@@ -1,3 +1,4 @@
+use crate::models::report::{Output, Report};
 use clap_complete::{generate, shells};
 
 #[derive(Debug, clap::Args)]
@@ -7,13 +8,15 @@ pub struct CompletionsCmd {
 }
 
 impl super::Run for CompletionsCmd {
-    fn run(&self) -> anyhow::Result<()> {
-        generate(
-            self.shell,
-            &mut <super::nedots::RootCmd as clap::CommandFactory>::command(),
-            "nedots",
-            &mut std::io::stdout(),
-        );
-        Ok(())
+    fn run(&self) -> anyhow::Result<Report> {
+        let mut cmd = <super::nedots::RootCmd as clap::CommandFactory>::command();
+        if !Output::is_json() {
+            generate(self.shell, &mut cmd, "nedots", &mut std::io::stdout());
+            return Ok(Report::default());
+        }
+
+        let mut script = Vec::new();
+        generate(self.shell, &mut cmd, "nedots", &mut script);
+        Report::with_data(&String::from_utf8(script)?)
     }
 }
@@ -0,0 +1,355 @@
+//! Commands to inspect & validate `nedots.yml`.
+
+use crate::{
+    models::{
+        config,
+        diagnostic::Diagnostic,
+        format::Format,
+        layer::Layer,
+        report::{Action, Output, Report},
+    },
+    utils::yaml,
+    RootCmd,
+};
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct ConfigCmd {
+    #[command(subcommand)]
+    cmd: ConfigSubCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum ConfigSubCommand {
+    /// Check `nedots.yml` for problems, exits non-zero if any are found
+    Check,
+    /// Print `nedots.yml`
+    Show {
+        /// Merge every included file, & show where each entry came from
+        #[arg(short, long)]
+        resolved: bool,
+    },
+    /// Print the JSON Schema of `nedots.yml`, for editors to autocomplete &
+    /// validate with
+    Schema {
+        /// Write to this file instead
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print `nedots.yml` in another format, included files are left as they
+    /// are
+    Convert {
+        #[arg(value_enum)]
+        format: Format,
+
+        /// Write to this file instead
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl super::RunWith<RootCmd> for ConfigCmd {
+    /// `ConfigCmd` reads `nedots.yml` itself, rather than being initialized
+    /// with a `Config`, since `nedots.yml` may not be valid.
+    ///
+    /// * `root_args`: &RootCmd
+    fn run_with(&self, root_args: &RootCmd) -> anyhow::Result<Report> {
+        let path = super::config_path(root_args);
+        match &self.cmd {
+            ConfigSubCommand::Check => check(&path),
+            ConfigSubCommand::Show { resolved } => show(&path, *resolved),
+            ConfigSubCommand::Schema { output } => schema(output.as_deref()),
+            ConfigSubCommand::Convert { format, output } => {
+                convert(&path, *format, output.as_deref())
+            }
+        }
+    }
+}
+
+/// Print each problem found in `nedots.yml` @ `path`, or any file it
+/// includes, as `file:line:column`.
+///
+/// * `path`: &Path
+fn check(path: &Path) -> anyhow::Result<Report> {
+    /// A `Diagnostic`, & the file it was found in.
+    #[derive(serde::Serialize)]
+    struct Problem<'a> {
+        file: &'a Path,
+        #[serde(flatten)]
+        diagnostic: &'a Diagnostic,
+    }
+
+    let diagnostics = config::check(path)?;
+    let problems: Vec<Problem> = diagnostics
+        .iter()
+        .map(|(file, diagnostic)| Problem { file, diagnostic })
+        .collect();
+    let report = Report::with_data(&problems)?;
+
+    // With `--output json`, they're printed as the report's `data` instead.
+    if !Output::is_json() {
+        for (file, diagnostic) in &diagnostics {
+            let sep = match diagnostic.line {
+                Some(_) => ":",
+                None => ": ",
+            };
+            println!(
+                "{}{}{}",
+                console::style(file.display()).bold(),
+                sep,
+                diagnostic
+            );
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        return Err(report.invalid(format!(
+            "{} problem(s) found in `{}`",
+            diagnostics.len(),
+            path.display()
+        )));
+    }
+
+    log::info!("✅ {}", console::style("No problems found!").bold());
+    Ok(report)
+}
+
+/// Print `nedots.yml` @ `path` as it is, or merged with every file it
+/// includes, each entry annotated with the file it came from.
+///
+/// * `path`: &Path
+/// * `resolved`: bool
+fn show(path: &Path, resolved: bool) -> anyhow::Result<Report> {
+    let read = || {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read `{}`", path.display()))
+    };
+
+    if Output::is_json() {
+        let config = match resolved {
+            true => config::read_with_origins(path)?.0,
+            false => Layer::parse(path, read()?)?.config,
+        };
+        return Report::with_data(&config);
+    }
+
+    if !resolved {
+        print!("{}", read()?);
+        return Ok(Report::default());
+    }
+
+    let (config, origins) = config::read_with_origins(path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let origin = |origin: &Path| {
+        let origin = origin.strip_prefix(base).unwrap_or(origin);
+        console::style(format!("# {}", origin.display())).dim()
+    };
+
+    /// Print `lines`, with `comment` on the first.
+    fn annotate(lines: &[String], comment: impl std::fmt::Display) {
+        for (i, line) in lines.iter().enumerate() {
+            match i {
+                0 => println!("{}  {}", line, comment),
+                _ => println!("{}", line),
+            }
+        }
+    }
+
+    let scalar = |key: &str, value: serde_yaml::Value| -> anyhow::Result<Vec<String>> {
+        let mut map = serde_yaml::Mapping::new();
+        map.insert(key.into(), value);
+        Ok(serde_yaml::to_string(&map)?
+            .lines()
+            .map(str::to_string)
+            .collect())
+    };
+
+    if !config.include.is_empty() {
+        println!("include:");
+        for include in &config.include {
+            annotate(
+                &yaml::render_item(&include.as_str().into(), 2)?,
+                origin(&config.file),
+            );
+        }
+    }
+
+    let dirs = [
+        ("root", &config.root, &origins.root),
+        ("dots_dir", &config.dots_dir, &origins.dots_dir),
+        ("backup_dir", &config.backup_dir, &origins.backup_dir),
+    ];
+    for (key, dir, from) in dirs {
+        if let Some(from) = from {
+            let value = serde_yaml::to_value(dir)?;
+            annotate(&scalar(key, value)?, origin(from));
+        }
+    }
+
+    if let Some(from) = &origins.remote {
+        annotate(
+            &scalar("remote", config.remote.clone().into())?,
+            origin(from),
+        );
+    }
+
+    if let Some(from) = &origins.submodules {
+        annotate(
+            &scalar("submodules", config.submodules.into())?,
+            origin(from),
+        );
+    }
+
+    if !config.mirrors.is_empty() {
+        println!("mirrors:");
+        for (mirror, from) in config.mirrors.iter().zip(&origins.mirrors) {
+            annotate(
+                &yaml::render_item(&mirror.as_str().into(), 2)?,
+                origin(from),
+            );
+        }
+    }
+
+    println!("sources:");
+    for (source, from) in config.sources.iter().zip(&origins.sources) {
+        let value = serde_yaml::to_value(source)?;
+        annotate(&yaml::render_item(&value, 2)?, origin(from));
+    }
+
+    println!("git_repos:");
+    for (repo, from) in config.git_repos.iter().zip(&origins.git_repos) {
+        let value = serde_yaml::to_value(repo)?;
+        annotate(&yaml::render_item(&value, 2)?, origin(from));
+    }
+
+    if !config.source_hooks.is_empty() {
+        println!("source_hooks:");
+        for (source, hooks) in &config.source_hooks {
+            let lines = scalar(&source.to_string_lossy(), serde_yaml::to_value(hooks)?)?;
+            let lines: Vec<String> = lines.iter().map(|l| format!("  {}", l)).collect();
+            annotate(&lines, origin(&origins.source_hooks[source]));
+        }
+    }
+
+    if !config.ignore.is_empty() {
+        println!("ignore:");
+        for (pattern, from) in config.ignore.iter().zip(&origins.ignore) {
+            annotate(
+                &yaml::render_item(&pattern.as_str().into(), 2)?,
+                origin(from),
+            );
+        }
+    }
+
+    if !config.packages.is_empty() {
+        println!("packages:");
+        for (manager, packages) in &config.packages {
+            println!("  {}:", manager);
+            for (package, from) in packages.iter().zip(&origins.packages[manager]) {
+                annotate(
+                    &yaml::render_item(&package.as_str().into(), 4)?,
+                    origin(from),
+                );
+            }
+        }
+    }
+
+    if let Some(from) = &origins.hooks {
+        annotate(
+            &scalar("hooks", serde_yaml::to_value(&config.hooks)?)?,
+            origin(from),
+        );
+    }
+
+    Ok(Report::default())
+}
+
+/// Print the JSON Schema of `nedots.yml`, or write it to `output`.
+///
+/// * `output`: Option<&Path>
+fn schema(output: Option<&Path>) -> anyhow::Result<Report> {
+    let schema = config::schema()?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, schema)?;
+            log::info!(
+                "🗒️ Schema written to {}",
+                console::style(path.display()).bold()
+            );
+
+            let mut report = Report::default();
+            report.push(Action::Written {
+                path: path.to_path_buf(),
+            });
+            Ok(report)
+        }
+        None if Output::is_json() => {
+            Report::with_data(&serde_json::from_str::<serde_json::Value>(&schema)?)
+        }
+        None => {
+            println!("{}", schema);
+            Ok(Report::default())
+        }
+    }
+}
+
+/// Print `nedots.yml` @ `path` in `format`, or write it to `output`. Comments
+/// aren't kept, other than pointing editors at the schema.
+///
+/// * `path`: &Path
+/// * `format`: Format
+/// * `output`: Option<&Path>
+fn convert(path: &Path, format: Format, output: Option<&Path>) -> anyhow::Result<Report> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read `{}`", path.display()))?;
+    let layer = Layer::parse(path, raw)?;
+
+    let modeline = format.schema_modeline(config::SCHEMA_FILE);
+    let converted = modeline.unwrap_or_default() + &format.serialize(&layer.config)?;
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, converted)?;
+            log::info!(
+                "🔁 Converted {} to {} @ {}",
+                console::style(path.display()).bold(),
+                format,
+                console::style(output.display()).bold()
+            );
+
+            let mut report = Report::default();
+            report.push(Action::Written {
+                path: output.to_path_buf(),
+            });
+            Ok(report)
+        }
+        None if Output::is_json() => Report::with_data(&converted),
+        None => {
+            print!("{}", converted);
+            Ok(Report::default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{exit, exit_code};
+
+    #[test]
+    fn check_exits_as_invalid_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("nedots.yml");
+        std::fs::write(&path, "remote: x\nsorces: []\n").unwrap();
+
+        let err = super::check(&path).unwrap_err();
+        assert_eq!(exit_code(&err), exit::CONFIG);
+        assert_eq!(
+            err.to_string(),
+            format!("1 problem(s) found in `{}`", path.display())
+        );
+
+        std::fs::write(&path, "remote: x\nsources: []\n").unwrap();
+        assert!(super::check(&path).is_ok());
+    }
+}
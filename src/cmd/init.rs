@@ -1,98 +1,184 @@
 use crate::{
-    models::{config, git_repo::GitRepo},
+    models::{
+        config::{self, Config},
+        format::Format,
+        git_repo::{CloneFilter, GitRepo},
+        report::{Action, Report},
+    },
     utils::{paths::MakeDirs, spinner::Spinner},
+    RootCmd,
 };
-use directories::BaseDirs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, clap::Args)]
 pub struct InitCmd {
-    /// Migrating files from this user
-    #[arg(short, long)]
-    from_user: Option<String>,
+    #[command(flatten)]
+    clone: CloneArgs,
 
-    /// Remote git repository to clone, `root` arg determines the destination
-    remote: String,
+    /// Format of the sample config, if it doesn't exist yet
+    #[arg(long, value_enum)]
+    config_format: Option<Format>,
 }
 
-impl super::Run for InitCmd {
-    fn run(&self) -> anyhow::Result<()> {
-        let root_dir = BaseDirs::new()
-            .expect("No BaseDirs")
-            .data_local_dir()
-            .join("nedots");
-
-        if !root_dir.exists() {
-            log::trace!("Initializing {} @ {}...", &self.remote, &root_dir.display());
-            let spinner = Spinner::start();
-            let repo = GitRepo::new(&self.remote, &root_dir);
-
-            spinner.set_msg(&format!(
-                " Initializing {} @ {}...",
-                console::style(&self.remote).blue(),
-                console::style(&root_dir.display()).blue(),
-            ));
-            repo.clone()?;
-            repo.init_submodules()?;
-
-            spinner.finish();
-        } else {
-            log::debug!("{} @ {} exists", self.remote, &root_dir.display());
-        }
+/// How to clone the root repository, shared by `init` & `bootstrap`.
+#[derive(Debug, clap::Args)]
+pub struct CloneArgs {
+    /// Create a shallow clone, truncated to this many commits
+    #[arg(long)]
+    depth: Option<u32>,
 
-        if let Some(user) = &self.from_user {
-            migrate_user(user, &root_dir)?;
-        }
+    /// Create a partial clone, objects are fetched when needed
+    #[arg(long, value_enum)]
+    filter: Option<CloneFilter>,
 
-        // Make backup directory
-        let path = Path::new(&root_dir).join(config::DEFAULT_BACKUP_DIR);
-        path.make_all_dirs()?;
+    /// Remote git repository to clone, `root` arg determines the destination
+    pub remote: String,
 
-        // Create `$XDG_CONFIG_HOME/nedots` & create a sample config file..
-        init_config()?;
+    /// Mirrors of `remote`, cloned from if `remote` is unreachable
+    mirrors: Vec<String>,
+}
 
-        log::info!("✅ {}", console::style("Initialized!").bold());
-        Ok(())
+impl CloneArgs {
+    /// Clone `remote` into `root_dir`, unless it already exists. Returns
+    /// whether it was cloned.
+    ///
+    /// * `root_dir`: &Path
+    pub fn clone_into(&self, root_dir: &Path) -> anyhow::Result<bool> {
+        if root_dir.exists() {
+            log::debug!("{} @ {} exists", self.remote, root_dir.display());
+            return Ok(false);
+        }
+
+        log::trace!("Initializing {} @ {}...", &self.remote, root_dir.display());
+        let spinner = Spinner::start();
+        let repo = GitRepo {
+            mirrors: self.mirrors.clone(),
+            depth: self.depth,
+            filter: self.filter,
+            ..GitRepo::new(&self.remote, root_dir)
+        };
+
+        spinner.set_msg(&format!(
+            " Initializing {} @ {}...",
+            console::style(&self.remote).blue(),
+            console::style(root_dir.display()).blue(),
+        ));
+        repo.clone()?;
+        repo.init_submodules()?;
+
+        spinner.finish();
+        Ok(true)
     }
 }
 
-fn migrate_user(from_user: &str, root_dir: &Path) -> anyhow::Result<()> {
-    log::trace!("Migrating from `{}`", from_user);
+impl super::Initialize<Config, RootCmd> for InitCmd {
+    /// `nedots.yml` may not exist yet, in which case `root` etc. are only
+    /// determined by `root_args` & defaults, & the sample is written in
+    /// `config_format`.
+    ///
+    /// * `root_args`: &RootCmd
+    fn init(&self, root_args: &RootCmd) -> anyhow::Result<Config> {
+        let path = super::config_path(root_args);
+        let mut config = match path.exists() {
+            true => config::read(&path)?,
+            false => Config {
+                file: match self.config_format {
+                    Some(format) => path.with_extension(format.extension()),
+                    None => path,
+                },
+                ..Default::default()
+            },
+        };
+
+        root_args.set_dirs(&mut config)?;
+        log::debug!("Initializing with {:#?}", config);
+        Ok(config)
+    }
+}
 
-    let path = root_dir.join(config::DEFAULT_DOTS_DIR);
-    let from_path = path.join(format!("home/{}", from_user));
-    let to_path = crate::utils::join_paths(&path, Path::new(env!("HOME")));
+impl super::RunWith<Config> for InitCmd {
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let mut report = Report::default();
+        if self.clone.clone_into(&config.root)? {
+            report.push(Action::Cloned {
+                remote: self.clone.remote.clone(),
+                path: config.root.clone(),
+            });
+        }
 
-    log::trace!(
-        "Renaming `{}` -> `{}`",
-        from_path.display(),
-        to_path.display()
-    );
-    std::fs::rename(from_path, to_path)?;
+        // Make backup directory
+        config.backup_dir.make_all_dirs()?;
+
+        // A config committed to the root repository is used as is, otherwise
+        // create `$XDG_CONFIG_HOME/nedots` & create a sample config file..
+        let repo_config = Format::find(&config.root.join(config::REPO_CONFIG_FILE));
+        match repo_config {
+            Some(path) if !config.file.exists() || config.file == path => log::info!(
+                "🗒️ Using config committed @ {}",
+                console::style(path.display()).bold()
+            ),
+            _ => {
+                let sample = Config {
+                    remote: self.clone.remote.clone(),
+                    mirrors: self.clone.mirrors.clone(),
+                    ..config::get_sample()
+                };
+                for path in init_config(config, sample)? {
+                    report.push(Action::Written { path });
+                }
+            }
+        }
 
-    Ok(())
+        log::info!("✅ {}", console::style("Initialized!").bold());
+        Ok(report)
+    }
 }
 
-fn init_config() -> anyhow::Result<()> {
-    let config_dir = BaseDirs::new()
-        .expect("No BaseDirs")
-        .config_dir()
-        .join("nedots");
+/// Write the schema, & `sample` to `nedots.yml`, or `nedots.toml` etc., if it
+/// doesn't exist. Directories that aren't the default are recorded in the
+/// sample, so that `--root` etc. needn't be given every time. Returns the
+/// files written.
+///
+/// * `config`: &Config
+/// * `sample`: Config, written as is, other than directories.
+pub(crate) fn init_config(config: &Config, mut sample: Config) -> anyhow::Result<Vec<PathBuf>> {
+    let config_file = &config.file;
+    let config_dir = config_file.parent().unwrap_or(Path::new(""));
+    config_dir.make_all_dirs()?;
+
+    // Always rewrite the schema, so that it matches this version of `nedots`.
+    let schema_file = config_dir.join(config::SCHEMA_FILE);
+    log::trace!("Writing `{}`...", schema_file.display());
+    std::fs::write(&schema_file, config::schema()?)?;
+    let mut written = vec![schema_file];
 
-    let config_file = config_dir.join("nedots.yml");
     if !config_file.exists() {
         // If nedots.yml isn't yet present, we'll create an example file.
         log::trace!("Creating sample `{}`...", config_file.display());
 
-        let yaml = serde_yaml::to_string(&config::get_sample())?;
-        config_dir.make_all_dirs()?;
-        std::fs::write(&config_file, yaml)?;
+        if config.root != config::default_root() {
+            sample.root = config.root.clone();
+        }
+        if config.dots_dir != config.root.join(config::DEFAULT_DOTS_DIR) {
+            sample.dots_dir = config.dots_dir.clone();
+        }
+        if config.backup_dir != config.root.join(config::DEFAULT_BACKUP_DIR) {
+            sample.backup_dir = config.backup_dir.clone();
+        }
+
+        // Editors, e.g. using `yaml-language-server` or `taplo`, will pick up
+        // the schema.
+        let format = Format::from_path(config_file);
+        let modeline = format.schema_modeline(config::SCHEMA_FILE);
+        let raw = modeline.unwrap_or_default() + &format.serialize(&sample)?;
+        std::fs::write(config_file, raw)?;
 
         log::info!(
             "🗒️ Sample config can be found @ {}",
             console::style(config_file.display()).bold()
         );
+        written.push(config_file.clone());
     }
 
-    Ok(())
+    Ok(written)
 }
@@ -1,11 +1,21 @@
-use crate::{models::config::Config, utils::paths::ResolvePath};
+use crate::{
+    models::{config::Config, report::Report},
+    utils::matcher::Matcher,
+    Nedots, RootCmd,
+};
+use std::path::PathBuf;
 
 #[derive(Debug, clap::Args)]
 pub struct InstallCmd {
-    /// Only gather this source. Any unique portion of a path in `sources` is
-    /// valid. E.g. given a list of [ "/home/user/.bashrc", "/home/user/.zshrc" ],
-    /// ".bashrc" or ".zshrc" may be used as a key.
-    key: Option<String>,
+    /// Only install these sources. A key may be an exact path, any unique
+    /// ending or portion of a path in `sources`, a glob, or a fuzzy match.
+    /// E.g. given a list of [ ".config/bspwm", ".zshrc" ], "bspwm", ".config/*"
+    /// or "zsh" may be used as keys.
+    keys: Vec<String>,
+
+    /// Stop at the first source or repository that fails to install
+    #[arg(long)]
+    fail_fast: bool,
 }
 
 impl super::ValidateConfig for InstallCmd {
@@ -13,7 +23,7 @@ impl super::ValidateConfig for InstallCmd {
     /// need to validate `sources`, etc.
     ///
     /// * `config`: mut Config
-    fn validate(&self, mut config: Config) -> anyhow::Result<Config> {
+    fn validate(&self, mut config: Config, _: &RootCmd) -> anyhow::Result<Config> {
         config = config.resolve_dirs();
         log::debug!("Resolved {:#?}", config);
         Ok(config)
@@ -21,46 +31,13 @@ impl super::ValidateConfig for InstallCmd {
 }
 
 impl super::RunWith<Config> for InstallCmd {
-    fn run_with(&self, config: &Config) -> anyhow::Result<()> {
-        const SUCCESS_MSG: &str = "👍 Installed";
-
-        if let Some(key) = &self.key {
-            if let Some(val) = config.get_sources_as_hashmap().get(key.as_str()) {
-                let dst = &val.prepend_home();
-                let src = crate::utils::join_paths(&config.dots_dir, dst);
-
-                crate::ops::copy(&src, dst)?;
-                log::info!(
-                    "{} `{}`",
-                    SUCCESS_MSG,
-                    console::style(dst.display()).green().bold(),
-                );
-            } else {
-                log::error!("❌ `{}` not found", key);
-            }
-        } else {
-            for source in &config.sources {
-                let dst = &source.prepend_home();
-                let src = crate::utils::join_paths(&config.dots_dir, dst);
-
-                crate::ops::copy(&src, dst)?;
-                log::info!(
-                    "{} `{}`",
-                    SUCCESS_MSG,
-                    console::style(dst.display()).green().bold(),
-                );
-            }
-
-            for repo in &config.git_repos {
-                repo.clone()?;
-                log::info!(
-                    "{} `{}`",
-                    SUCCESS_MSG,
-                    console::style(repo.path.display()).green().bold(),
-                );
-            }
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let nedots = Nedots::prepared(config.clone(), self.fail_fast);
+        if self.keys.is_empty() {
+            return Ok(nedots.install()?);
         }
 
-        Ok(())
+        let matcher = Matcher::new(config.sources.iter().map(PathBuf::as_path));
+        Ok(nedots.install_sources(&matcher.select(&self.keys)?)?)
     }
 }
@@ -0,0 +1,230 @@
+//! Command to list everything `nedots` manages, & the state of each.
+
+use crate::{
+    models::{
+        config::Config,
+        git_repo::GitRepo,
+        report::{Output, Report},
+    },
+    utils::{
+        matcher::Matcher,
+        paths::{self, Metadata, ResolvePath},
+    },
+    RootCmd,
+};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct ListCmd {
+    /// Same as `--output json`, kept for scripts that predate it
+    #[arg(long, hide = true)]
+    pub(crate) json: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    File,
+    Dir,
+    Repo,
+    /// Neither the local path, nor a copy in `dots`, exists.
+    Missing,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            Kind::File => "file",
+            Kind::Dir => "dir",
+            Kind::Repo => "repo",
+            Kind::Missing => "missing",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// A source or git repository, & its state.
+#[derive(Debug, serde::Serialize)]
+struct Entry {
+    /// As it appears in `nedots.yml`.
+    source: PathBuf,
+    path: PathBuf,
+    kind: Kind,
+    /// Unknown if it couldn't be measured, or if it's missing.
+    size: Option<u64>,
+    /// Exists @ `path`.
+    local: bool,
+    /// Exists in `dots`.
+    dots: bool,
+    /// Keys that select this source, e.g. `install KEY`.
+    keys: Vec<String>,
+}
+
+impl super::ValidateConfig for ListCmd {
+    /// `ListCmd` reports on `sources` that don't resolve, so only core
+    /// directories are resolved here.
+    ///
+    /// * `config`: mut Config
+    fn validate(&self, mut config: Config, _: &RootCmd) -> anyhow::Result<Config> {
+        config = config.resolve_dirs();
+        log::debug!("Resolved {:#?}", config);
+        Ok(config)
+    }
+}
+
+impl super::RunWith<Config> for ListCmd {
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let matcher = Matcher::new(config.sources.iter().map(PathBuf::as_path));
+
+        let mut entries = Vec::new();
+        for source in &config.sources {
+            let path = resolve(source);
+            let dots = config.dots_path(source).unwrap_or_default();
+
+            entries.push(entry(source, &path, &dots, matcher.keys(source)));
+        }
+
+        for repo in &config.git_repos {
+            let path = resolve(&repo.path);
+            let dots = config
+                .root
+                .join(config.submodule_path(&GitRepo::new(&repo.remote, &path)));
+
+            let mut entry = entry(&repo.path, &path, &dots, Vec::new());
+            if entry.local || entry.dots {
+                entry.kind = Kind::Repo;
+            }
+            entries.push(entry);
+        }
+
+        if Output::is_json() {
+            return Report::with_data(&entries);
+        }
+
+        print(&entries);
+        Ok(Report::default())
+    }
+}
+
+/// Resolve `path`, if it doesn't exist it's assumed to be relative to $HOME,
+/// & if it can't be expanded it's left as it is.
+///
+/// * `path`: &Path
+fn resolve(path: &Path) -> PathBuf {
+    path.resolve_path()
+        .or_else(|_| paths::expand_path(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Inspect local `path` & its copy @ `dots`. Whichever exists determines
+/// `Kind` & size, preferring `path`.
+///
+/// * `source`: &Path, as it appears in `nedots.yml`.
+/// * `path`: &Path, resolved.
+/// * `dots`: &Path, copy in `dots`.
+/// * `keys`: Vec<String>
+fn entry(source: &Path, path: &Path, dots: &Path, keys: Vec<String>) -> Entry {
+    let (local, in_dots) = (path.exists(), dots.exists());
+    let existing = if local { path } else { dots };
+
+    let kind = match (local || in_dots, existing.is_dir()) {
+        (false, _) => Kind::Missing,
+        (true, true) => Kind::Dir,
+        (true, false) => Kind::File,
+    };
+
+    let size = match local || in_dots {
+        true => existing
+            .get_size()
+            .inspect_err(|err| log::warn!("⚠️ Can't size `{}`, {}", existing.display(), err))
+            .ok(),
+        false => None,
+    };
+
+    Entry {
+        source: source.to_path_buf(),
+        path: path.to_path_buf(),
+        kind,
+        size,
+        local,
+        dots: in_dots,
+        keys,
+    }
+}
+
+fn print(entries: &[Entry]) {
+    fn tick(exists: bool) -> console::StyledObject<&'static str> {
+        match exists {
+            true => console::style("✓").green(),
+            false => console::style("✗").red(),
+        }
+    }
+
+    let width = entries
+        .iter()
+        .map(|e| e.path.display().to_string().len())
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{:<7}  {:>10}  LOCAL  DOTS  {:<width$}  KEYS",
+        "TYPE", "SIZE", "PATH"
+    );
+    for entry in entries {
+        println!(
+            "{:<7}  {:>10}  {}      {}     {}  {}",
+            entry.kind.to_string(),
+            entry
+                .size
+                .map(|size| indicatif::HumanBytes(size).to_string())
+                .unwrap_or("-".to_string()),
+            tick(entry.local),
+            tick(entry.dots),
+            console::style(format!("{:<width$}", entry.path.display())).blue(),
+            entry.keys.join(", "),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entry, Kind};
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn entries_prefer_local_and_size_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (local, dots) = (tmp.path().join("local"), tmp.path().join("dots"));
+        std::fs::create_dir_all(local.join("nvim")).unwrap();
+        std::fs::write(local.join("nvim/init.lua"), "1234").unwrap();
+        std::fs::write(dots.with_extension("conf"), "12").unwrap();
+
+        // A broken symlink in a directory is counted, not an error.
+        symlink(tmp.path().join("nowhere"), local.join("nvim/broken")).unwrap();
+        let dir = entry(
+            ".config/nvim".as_ref(),
+            &local.join("nvim"),
+            &dots,
+            Vec::new(),
+        );
+        assert!(matches!(dir.kind, Kind::Dir));
+        assert!(dir.local && !dir.dots);
+        assert!(dir.size.is_some_and(|size| size > 4));
+
+        // Only in `dots`, so that's what's measured.
+        let path = local.join("a.conf");
+        let file = entry(
+            "a.conf".as_ref(),
+            &path,
+            &dots.with_extension("conf"),
+            Vec::new(),
+        );
+        assert!(matches!(file.kind, Kind::File));
+        assert!(!file.local && file.dots);
+        assert_eq!(file.size, Some(2));
+
+        let missing = entry("b.conf".as_ref(), &local.join("b.conf"), &dots, Vec::new());
+        assert!(matches!(missing.kind, Kind::Missing));
+        assert_eq!(missing.size, None);
+    }
+}
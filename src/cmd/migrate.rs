@@ -0,0 +1,202 @@
+//! Command to migrate `dots` from the layout where files were kept at their
+//! absolute path, e.g. `dots/home/alice/.bashrc`, to one that's independent of
+//! the user & their $HOME, e.g. `dots/HOME/.bashrc`.
+
+use crate::{
+    models::{
+        config::{Config, HOME_PREFIX, ROOT_PREFIX},
+        report::{Action, Report},
+    },
+    utils::paths::{self, MakeDirs},
+    RootCmd,
+};
+use directories::BaseDirs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct MigrateCmd {
+    /// Home directory that `dots` were gathered from, e.g. `/home/alice`,
+    /// defaults to $HOME
+    #[arg(long)]
+    from_home: Option<PathBuf>,
+}
+
+impl super::ValidateConfig for MigrateCmd {
+    /// `MigrateCmd` only needs core directories.
+    ///
+    /// * `config`: mut Config
+    fn validate(&self, mut config: Config, _: &RootCmd) -> anyhow::Result<Config> {
+        config = config.resolve_dirs();
+        log::debug!("Resolved {:#?}", config);
+        Ok(config)
+    }
+}
+
+impl super::RunWith<Config> for MigrateCmd {
+    /// Move the old home directory to `HOME`, then everything else that isn't
+    /// already migrated to `ROOT`. Moves are made with `git mv` where possible,
+    /// so that submodules follow, & left for `sync` to commit.
+    ///
+    /// * `config`: &Config
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let home = match &self.from_home {
+            Some(home) => home.clone(),
+            None => BaseDirs::new()
+                .expect("No BaseDirs")
+                .home_dir()
+                .to_path_buf(),
+        };
+
+        let root = config.root_repo();
+        let dots = &config.dots_dir;
+        let mut report = Report::default();
+
+        let old_home = crate::utils::join_paths(dots, &home);
+        if old_home.exists() {
+            report.push(move_path(config, &old_home, &dots.join(HOME_PREFIX))?);
+
+            // Leave behind no empty parents, e.g. `dots/home`.
+            for dir in old_home.ancestors().skip(1) {
+                if dir == dots || std::fs::remove_dir(dir).is_err() {
+                    break;
+                }
+            }
+        }
+
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(dots)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|path| !is_migrated(config, path))
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            let name = path.file_name().unwrap_or_default();
+            report.push(move_path(
+                config,
+                &path,
+                &dots.join(ROOT_PREFIX).join(name),
+            )?);
+        }
+
+        if report.actions.is_empty() {
+            log::info!("👌 Nothing to migrate");
+        } else {
+            log::info!(
+                "✅ {} Review the changes in {}, then `nedots sync` to commit them",
+                console::style("Migrated!").bold(),
+                console::style(root.path.display()).blue()
+            );
+        }
+
+        Ok(report)
+    }
+}
+
+/// Whether top-level `path` in `dots` is `HOME`, `ROOT`, or named after a
+/// variable that `sources` are kept under, e.g. `XDG_CONFIG_HOME`.
+///
+/// * `config`: &Config
+/// * `path`: &Path
+fn is_migrated(config: &Config, path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name == HOME_PREFIX
+        || name == ROOT_PREFIX
+        || config.sources.iter().any(|source| {
+            paths::split_var(&source.to_string_lossy()).is_some_and(|(var, _)| var == name)
+        })
+}
+
+/// Move `from` to `to`, with `git mv` if `from` is tracked.
+///
+/// * `config`: &Config
+/// * `from`: &Path
+/// * `to`: &Path, mustn't exist.
+fn move_path(config: &Config, from: &Path, to: &Path) -> anyhow::Result<Action> {
+    if to.exists() {
+        anyhow::bail!(
+            "Can't move `{}`, `{}` already exists",
+            from.display(),
+            to.display()
+        );
+    }
+
+    if let Some(parent) = to.parent() {
+        parent.make_all_dirs()?;
+    }
+
+    let root = config.root_repo();
+    if root.is_tracked(from) {
+        root.mv(from, to)?;
+    } else {
+        log::debug!("`{}` isn't tracked, renaming", from.display());
+        std::fs::rename(from, to)?;
+    }
+
+    log::info!(
+        "🚚 Moved `{}` -> `{}`",
+        console::style(from.display()).blue(),
+        console::style(to.display()).green().bold()
+    );
+    Ok(Action::Moved {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MigrateCmd;
+    use crate::{
+        models::config::Config,
+        utils::testing::{commit_files, git_output, init_repo},
+        RunWith,
+    };
+
+    #[test]
+    fn old_layout_is_moved_with_history() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        init_repo(&root);
+        commit_files(
+            &root,
+            &[
+                ("dots/home/alice/.bashrc", "alias ll='ls -l'"),
+                ("dots/etc/hostname", "laptop"),
+                ("dots/XDG_CONFIG_HOME/app/app.conf", "theme = dark"),
+                ("dots/OPT/tool.conf", "x = 1"),
+            ],
+        );
+
+        let config = Config {
+            root: root.clone(),
+            dots_dir: root.join("dots"),
+            sources: vec!["$XDG_CONFIG_HOME/app".into()],
+            ..Default::default()
+        };
+        let cmd = MigrateCmd {
+            from_home: Some("/home/alice".into()),
+        };
+        let report = cmd.run_with(&config).unwrap();
+        assert_eq!(report.actions.len(), 3);
+
+        let dots = root.join("dots");
+        assert!(dots.join("HOME/.bashrc").exists());
+        assert!(dots.join("ROOT/etc/hostname").exists());
+        assert!(dots.join("ROOT/OPT/tool.conf").exists());
+        assert!(dots.join("XDG_CONFIG_HOME/app/app.conf").exists());
+        assert!(!dots.join("home").exists());
+
+        // Moves are staged as renames, so history follows them.
+        let staged = git_output(&root, &["diff", "--cached", "--name-status"]);
+        let mut renames: Vec<&str> = staged.lines().collect();
+        renames.sort();
+        assert_eq!(
+            renames,
+            [
+                "R100\tdots/OPT/tool.conf\tdots/ROOT/OPT/tool.conf",
+                "R100\tdots/etc/hostname\tdots/ROOT/etc/hostname",
+                "R100\tdots/home/alice/.bashrc\tdots/HOME/.bashrc",
+            ]
+        );
+    }
+}
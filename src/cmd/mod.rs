@@ -1,21 +1,34 @@
+pub mod add;
 pub mod backup;
+pub mod bootstrap;
 pub mod clean;
 pub mod completions;
+pub mod config;
 pub mod init;
 pub mod install;
+pub mod list;
+pub mod migrate;
 pub mod nedots;
+pub mod packages;
+pub mod remove;
+pub mod schedule;
 pub mod sync;
+pub mod watch;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::{
-    models::config::{self, Config},
-    Execute, ExecuteWith, Initialize, RootCmd, Run, RunWith,
+    models::{
+        config::{self as config_model, Config},
+        format::Format,
+    },
+    utils::paths,
+    Execute, ExecuteWith, Initialize, Report, RootCmd, Run, RunWith,
 };
 
 pub trait ValidateConfig {
-    fn validate(&self, mut config: Config) -> anyhow::Result<Config> {
-        config = config.resolve_paths();
+    fn validate(&self, mut config: Config, root_args: &RootCmd) -> anyhow::Result<Config> {
+        config = config.resolve_paths(root_args.lenient)?;
         log::debug!("Resolved {:#?}", config);
         Ok(config)
     }
@@ -23,25 +36,70 @@ pub trait ValidateConfig {
 
 impl<T: ValidateConfig> Initialize<Config, RootCmd> for T {
     fn init(&self, root_args: &RootCmd) -> anyhow::Result<Config> {
-        let base_dirs = directories::BaseDirs::new().expect("No BaseDirs");
-        let config_path = match Path::new(&root_args.config).canonicalize() {
-            Ok(path) => path,
-            Err(_) => base_dirs.config_dir().join(&root_args.config),
-        };
-
-        let mut config = config::read(&config_path)?;
-        config.root = base_dirs.data_local_dir().join("nedots");
+        let mut config = config_model::read(&config_path(root_args))?;
+        root_args.set_dirs(&mut config)?;
 
         log::debug!("Raw {:#?}", config);
-        self.validate(config)
+        self.validate(config, root_args)
     }
 }
 
+/// Path of `nedots.yml`. The user's, see `user_config_path`, unless it doesn't
+/// exist or `--repo-config` is given, in which case the one committed to the
+/// root repository is used, see `repo_config_path`.
+///
+/// * `root_args`: &RootCmd
+pub(crate) fn config_path(root_args: &RootCmd) -> PathBuf {
+    let user = user_config_path(root_args);
+    if user.exists() && !root_args.repo_config {
+        return user;
+    }
+
+    let repo = repo_config_path(root_args);
+    match repo.exists() || root_args.repo_config {
+        true => repo,
+        false => user,
+    }
+}
+
+/// `--config` is either a path to an existing file, or relative to
+/// $XDG_CONFIG_HOME. If it doesn't exist, the same file with any other
+/// extension, e.g. `nedots.toml`, is used instead.
+///
+/// * `root_args`: &RootCmd
+pub(crate) fn user_config_path(root_args: &RootCmd) -> PathBuf {
+    let path = match Path::new(&root_args.config).canonicalize() {
+        Ok(path) => return path,
+        Err(_) => directories::BaseDirs::new()
+            .expect("No BaseDirs")
+            .config_dir()
+            .join(&root_args.config),
+    };
+
+    Format::find(&path).unwrap_or(path)
+}
+
+/// `nedots.yml`, or `nedots.toml` etc., committed to the root repository. The
+/// root repository is where `--root` says, or the default, since `nedots.yml`
+/// hasn't been read yet.
+///
+/// * `root_args`: &RootCmd
+pub(crate) fn repo_config_path(root_args: &RootCmd) -> PathBuf {
+    let root = root_args
+        .root
+        .as_deref()
+        .and_then(|root| paths::expand_path(root).ok())
+        .unwrap_or_else(config_model::default_root);
+
+    let path = root.join(config_model::REPO_CONFIG_FILE);
+    Format::find(&path).unwrap_or(path)
+}
+
 impl<T> Execute for T
 where
     T: clap::Args + Run,
 {
-    fn exec(&self) -> anyhow::Result<()> {
+    fn exec(&self) -> anyhow::Result<Report> {
         self.run()
     }
 }
@@ -50,12 +108,45 @@ impl<T> ExecuteWith<RootCmd, Config> for T
 where
     T: clap::Args + RunWith<Config> + Initialize<Config, RootCmd>,
 {
-    fn exec_with(&self, root_args: &RootCmd) -> anyhow::Result<()> {
+    fn exec_with(&self, root_args: &RootCmd) -> anyhow::Result<Report> {
         self.run_with(&self.init(root_args)?)
     }
 }
 
 // Use default `Config` validation.
+impl ValidateConfig for add::AddCmd {}
 impl ValidateConfig for backup::BackupCmd {}
 impl ValidateConfig for clean::CleanCmd {}
 impl ValidateConfig for sync::SyncCmd {}
+
+#[cfg(test)]
+mod tests {
+    use super::config_path;
+    use crate::RootCmd;
+    use clap::Parser;
+
+    #[test]
+    fn repo_config_is_used_without_user_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let root = dir.join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("nedots.toml"), "remote = \"x\"\n").unwrap();
+
+        let user = dir.join("nedots.yml");
+        let args = |extra: &[&str]| {
+            let (root, user) = (root.display().to_string(), user.display().to_string());
+            let args = ["nedots", "--root", &root, "-c", &user];
+            RootCmd::parse_from(args.iter().chain(extra))
+        };
+
+        assert_eq!(config_path(&args(&[])), root.join("nedots.toml"));
+
+        std::fs::write(&user, "remote: x\n").unwrap();
+        assert_eq!(config_path(&args(&[])), user.canonicalize().unwrap());
+        assert_eq!(
+            config_path(&args(&["--repo-config"])),
+            root.join("nedots.toml")
+        );
+    }
+}
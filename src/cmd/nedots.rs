@@ -1,6 +1,10 @@
 use super::{Execute, ExecuteWith};
-use crate::models::config::Config;
+use crate::models::{
+    config::Config,
+    report::{Output, Report},
+};
 use clap_verbosity_flag::Verbosity;
+use std::path::PathBuf;
 
 const DEFAULT_CONFIG: &str = "nedots/nedots.yml";
 
@@ -11,6 +15,31 @@ pub struct RootCmd {
     #[arg(short, long, default_value = DEFAULT_CONFIG)]
     pub config: String,
 
+    /// Use `nedots.yml` committed to the root repository, even if the config
+    /// file exists
+    #[arg(long, global = true, env = "NEDOTS_REPO_CONFIG")]
+    pub repo_config: bool,
+
+    /// Where the dots repository is cloned [default: $XDG_DATA_HOME/nedots]
+    #[arg(long, global = true, env = "NEDOTS_ROOT")]
+    pub root: Option<PathBuf>,
+
+    /// Where copies of `sources` are kept, relative to `root` [default: dots]
+    #[arg(long, global = true, env = "NEDOTS_DOTS_DIR")]
+    pub dots_dir: Option<PathBuf>,
+
+    /// Where backups are kept, relative to `root` [default: backups]
+    #[arg(long, global = true, env = "NEDOTS_BACKUP_DIR")]
+    pub backup_dir: Option<PathBuf>,
+
+    /// Skip `sources` & `git_repos` that don't exist, rather than failing
+    #[arg(long, global = true)]
+    pub lenient: bool,
+
+    /// How to print results, `json` prints them on stdout, & logs on stderr
+    #[arg(short, long, global = true, value_enum, default_value_t = Output::Human)]
+    pub output: Output,
+
     #[command(flatten)]
     pub verbose: Verbosity,
 
@@ -20,18 +49,58 @@ pub struct RootCmd {
 
 #[derive(Debug, clap::Subcommand)]
 pub(crate) enum SubCommand {
+    /// Add files, directories or git repositories to `nedots.yml`
+    Add(super::add::AddCmd),
     /// Backup local configuration files/(ne)dots
     Backup(super::backup::BackupCmd),
+    /// Clone, configure & install everything on a fresh machine
+    Bootstrap(super::bootstrap::BootstrapCmd),
     /// Remove temporary files, `dots` & `backups`
     Clean(super::clean::CleanCmd),
     /// Generate shell completions
     Completions(super::completions::CompletionsCmd),
+    /// Inspect & validate `nedots.yml`
+    Config(super::config::ConfigCmd),
     /// Initialize `nedots`
     Init(super::init::InitCmd),
     /// Install files & directories
     Install(super::install::InstallCmd),
+    /// List managed files, directories & git repositories
+    List(super::list::ListCmd),
+    /// Migrate `dots` to a layout that's independent of the user & $HOME
+    Migrate(super::migrate::MigrateCmd),
+    /// Install or snapshot packages of each package manager
+    Packages(super::packages::PackagesCmd),
+    /// Remove files, directories or git repositories from `nedots.yml`
+    Remove(super::remove::RemoveCmd),
+    /// Sync periodically, with a systemd timer or cron
+    Schedule(super::schedule::ScheduleCmd),
     /// Collect files & directories & sync with remote
     Sync(super::sync::SyncCmd),
+    /// Gather files & directories as they change
+    Watch(super::watch::WatchCmd),
+}
+
+impl RootCmd {
+    /// Apply `--root`, `--dots-dir` & `--backup-dir` to `config`, see
+    /// `Config::set_dirs`.
+    ///
+    /// * `config`: &mut Config
+    pub(crate) fn set_dirs(&self, config: &mut Config) -> anyhow::Result<()> {
+        config.set_dirs(
+            self.root.as_deref(),
+            self.dots_dir.as_deref(),
+            self.backup_dir.as_deref(),
+        )
+    }
+
+    /// `--output`, or `json` given `list --json`, its hidden alias.
+    pub(crate) fn output(&self) -> Output {
+        match &self.cmd {
+            Some(SubCommand::List(list_cmd)) if list_cmd.json => Output::Json,
+            _ => self.output,
+        }
+    }
 }
 
 impl super::Initialize<Config, RootCmd> for RootCmd {
@@ -41,18 +110,29 @@ impl super::Initialize<Config, RootCmd> for RootCmd {
 }
 
 impl super::RunWith<Config> for RootCmd {
-    fn run_with(&self, _: &Config) -> anyhow::Result<()> {
+    fn run_with(&self, _: &Config) -> anyhow::Result<Report> {
+        self.output().set();
+
         if let Some(cmd) = &self.cmd {
             match cmd {
+                SubCommand::Add(add_cmd) => add_cmd.exec_with(self),
                 SubCommand::Backup(backup_cmd) => backup_cmd.exec_with(self),
+                SubCommand::Bootstrap(bootstrap_cmd) => bootstrap_cmd.run_with(self),
                 SubCommand::Clean(clean_cmd) => clean_cmd.exec_with(self),
                 SubCommand::Completions(completions_cmd) => completions_cmd.exec(),
-                SubCommand::Init(init_cmd) => init_cmd.exec(),
+                SubCommand::Config(config_cmd) => config_cmd.run_with(self),
+                SubCommand::Init(init_cmd) => init_cmd.exec_with(self),
                 SubCommand::Install(install_cmd) => install_cmd.exec_with(self),
+                SubCommand::List(list_cmd) => list_cmd.exec_with(self),
+                SubCommand::Migrate(migrate_cmd) => migrate_cmd.exec_with(self),
+                SubCommand::Packages(packages_cmd) => packages_cmd.exec_with(self),
+                SubCommand::Remove(remove_cmd) => remove_cmd.exec_with(self),
+                SubCommand::Schedule(schedule_cmd) => schedule_cmd.run_with(self),
                 SubCommand::Sync(sync_cmd) => sync_cmd.exec_with(self),
+                SubCommand::Watch(watch_cmd) => watch_cmd.exec_with(self),
             }
         } else {
-            Ok(())
+            Ok(Report::default())
         }
     }
 }
@@ -63,4 +143,15 @@ mod test {
     fn verify() {
         <super::RootCmd as clap::CommandFactory>::command().debug_assert()
     }
+
+    #[test]
+    fn list_json_is_output_json() {
+        use super::{Output, RootCmd};
+        use clap::Parser;
+
+        let root_cmd = RootCmd::parse_from(["nedots", "list", "--json"]);
+        assert_eq!(root_cmd.output(), Output::Json);
+        let root_cmd = RootCmd::parse_from(["nedots", "list"]);
+        assert_eq!(root_cmd.output(), Output::Human);
+    }
 }
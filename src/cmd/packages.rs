@@ -0,0 +1,208 @@
+//! Commands to install the `packages` listed in `nedots.yml`, & to record
+//! those that are installed.
+
+use crate::{
+    models::{
+        config::{self, Config},
+        format::Format,
+        layer::Layer,
+        packages::Manager,
+        report::{Action, Report},
+    },
+    RootCmd,
+};
+use std::{collections::BTreeMap, ffi::OsString, path::Path};
+
+/// Written next to `nedots.yml` by `snapshot`, & included by it.
+const SNAPSHOT_FILE: &str = "packages";
+
+#[derive(Debug, clap::Args)]
+pub struct PackagesCmd {
+    #[command(subcommand)]
+    cmd: PackagesSubCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum PackagesSubCommand {
+    /// Install packages that aren't installed yet
+    Install {
+        /// Only use these package managers [default: all that are available]
+        #[arg(value_enum)]
+        managers: Vec<Manager>,
+
+        /// Print what would be installed, without installing it
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+    /// Record packages that are installed explicitly in `packages.yml`,
+    /// included by `nedots.yml`
+    Snapshot {
+        /// Only use these package managers [default: all that are available]
+        #[arg(value_enum)]
+        managers: Vec<Manager>,
+    },
+}
+
+impl super::ValidateConfig for PackagesCmd {
+    /// `PackagesCmd` doesn't touch `sources`, they needn't exist.
+    ///
+    /// * `config`: mut Config
+    fn validate(&self, mut config: Config, _: &RootCmd) -> anyhow::Result<Config> {
+        config = config.resolve_dirs();
+        log::debug!("Resolved {:#?}", config);
+        Ok(config)
+    }
+}
+
+impl super::RunWith<Config> for PackagesCmd {
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        match &self.cmd {
+            PackagesSubCommand::Install { managers, dry_run } => {
+                install(config, &available(managers), *dry_run)
+            }
+            PackagesSubCommand::Snapshot { managers } => snapshot(config, &available(managers)),
+        }
+    }
+}
+
+/// $PATH, where the programs of each `Manager` are found.
+fn env_path() -> OsString {
+    std::env::var_os("PATH").unwrap_or_default()
+}
+
+/// `managers`, or all of them if none are given, that are available. Those
+/// that were asked for but aren't available are warned about.
+///
+/// * `managers`: &[Manager]
+fn available(managers: &[Manager]) -> Vec<Manager> {
+    if managers.is_empty() {
+        return Manager::ALL
+            .into_iter()
+            .filter(|manager| manager.is_available(&env_path()))
+            .collect();
+    }
+
+    managers
+        .iter()
+        .copied()
+        .filter(|manager| {
+            let available = manager.is_available(&env_path());
+            if !available {
+                log::warn!("⚠️ Skipping {}, it isn't installed", manager);
+            }
+            available
+        })
+        .collect()
+}
+
+/// Install `packages` of each of `managers` that are missing.
+///
+/// * `config`: &Config
+/// * `managers`: &[Manager]
+/// * `dry_run`: bool
+fn install(config: &Config, managers: &[Manager], dry_run: bool) -> anyhow::Result<Report> {
+    let mut report = Report::default();
+    for (manager, wanted) in &config.packages {
+        if !managers.contains(manager) {
+            log::debug!("Skipping {}", manager);
+            continue;
+        }
+
+        let missing = manager.missing(wanted, &env_path())?;
+        if missing.is_empty() {
+            log::info!(
+                "👍 {} packages are up to date",
+                console::style(manager).bold()
+            );
+            continue;
+        }
+
+        let list = console::style(missing.join(" ")).green();
+        if dry_run {
+            log::info!(
+                "📦 {} would install {}",
+                console::style(manager).bold(),
+                list
+            );
+        } else {
+            log::info!("📦 {} installing {}", console::style(manager).bold(), list);
+            manager.install(&missing, &env_path())?;
+        }
+
+        report.push(Action::Installed {
+            manager: *manager,
+            packages: missing,
+            dry_run,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Record packages installed by each of `managers` in `packages.yml`, or
+/// `packages.toml` etc. to match `nedots.yml`, which includes it. Packages of
+/// other managers already in `packages.yml` are kept.
+///
+/// * `config`: &Config
+/// * `managers`: &[Manager]
+fn snapshot(config: &Config, managers: &[Manager]) -> anyhow::Result<Report> {
+    let format = Format::from_path(&config.file);
+    let file_name = format!("{}.{}", SNAPSHOT_FILE, format.extension());
+    let path = config.file.with_file_name(&file_name);
+
+    let mut packages = match path.exists() {
+        true => {
+            Layer::parse(&path, std::fs::read_to_string(&path)?)?
+                .config
+                .packages
+        }
+        false => BTreeMap::new(),
+    };
+
+    for manager in managers {
+        let installed = manager.installed(&env_path())?;
+        log::info!(
+            "📸 {} has {} package(s) installed",
+            console::style(manager).bold(),
+            installed.len()
+        );
+        packages.insert(*manager, installed);
+    }
+
+    let snapshot = Config {
+        packages,
+        ..Default::default()
+    };
+    let modeline = format.schema_modeline(config::SCHEMA_FILE);
+    std::fs::write(
+        &path,
+        modeline.unwrap_or_default() + &format.serialize(&snapshot)?,
+    )?;
+
+    if !config.include.contains(&file_name) {
+        include(&config.file, &file_name)?;
+    }
+
+    log::info!(
+        "✅ {} {}",
+        console::style("Snapshot written to").bold(),
+        console::style(path.display()).blue()
+    );
+
+    let mut report = Report::default();
+    report.push(Action::Written { path });
+    Ok(report)
+}
+
+/// Add `file_name` to `include` of `nedots.yml` @ `path`.
+///
+/// * `path`: &Path
+/// * `file_name`: &str
+fn include(path: &Path, file_name: &str) -> anyhow::Result<()> {
+    let raw = std::fs::read_to_string(path)?;
+    let raw = Format::from_path(path).append_to_list(&raw, "include", &file_name)?;
+
+    log::trace!("Writing `{}`...", path.display());
+    std::fs::write(path, raw)?;
+    Ok(())
+}
@@ -0,0 +1,240 @@
+//! Command to remove files, directories or git repositories from `nedots.yml`.
+//!
+//! Entries are removed from whichever files list them, `nedots.yml` or those
+//! it includes. YAML & TOML config files are edited in place, so comments
+//! & ordering are preserved.
+
+use crate::{
+    errors::Error,
+    models::{
+        config::Config,
+        layer,
+        report::{Action, Report},
+    },
+    utils::matcher::Matcher,
+    RootCmd,
+};
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct RemoveCmd {
+    /// Sources or git repositories to remove, see `install` for valid keys
+    #[arg(required = true)]
+    keys: Vec<String>,
+
+    /// Delete copies from `dots` too
+    #[arg(short, long)]
+    delete: bool,
+
+    /// Won't prompt you to confirm deleting copies of keys that aren't exact
+    /// paths, e.g. globs
+    #[arg(short = 'y', long)]
+    assumeyes: bool,
+}
+
+impl super::ValidateConfig for RemoveCmd {
+    /// `RemoveCmd` may remove `sources` that no longer exist, so only core
+    /// directories are resolved here.
+    ///
+    /// * `config`: mut Config
+    fn validate(&self, mut config: Config, _: &RootCmd) -> anyhow::Result<Config> {
+        config = config.resolve_dirs();
+        log::debug!("Resolved {:#?}", config);
+        Ok(config)
+    }
+}
+
+impl super::RunWith<Config> for RemoveCmd {
+    /// Remove each of `keys` from `sources` & `git_repos`, and optionally
+    /// from `dots_dir`.
+    ///
+    /// * `config`: &Config
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let mut report = Report::default();
+        // Entries may come from included files, so each file that lists them
+        // is edited.
+        let mut layers = layer::load(&config.file)?;
+        let mut changed = vec![false; layers.len()];
+
+        let matcher = Matcher::new(
+            config
+                .sources
+                .iter()
+                .chain(config.git_repos.iter().map(|repo| &repo.path))
+                .map(PathBuf::as_path),
+        );
+
+        let paths = matcher.select(&self.keys)?;
+        let inexact = self.keys.iter().any(|key| !matcher.is_exact(key));
+        if self.delete && inexact && !self.assumeyes {
+            confirm_delete(&paths)?;
+        }
+
+        for path in &paths {
+            let matches = |value: Option<&serde_yaml::Value>| {
+                value.and_then(|v| v.as_str()).map(Path::new) == Some(*path)
+            };
+
+            let mut removed = 0;
+            for (layer, changed) in layers.iter_mut().zip(&mut changed) {
+                let format = layer.format;
+                let (raw, sources) =
+                    format.remove_from_list(&layer.raw, "sources", |v| matches(Some(v)))?;
+                let (raw, git_repos) =
+                    format.remove_from_list(&raw, "git_repos", |v| matches(v.get("path")))?;
+
+                if sources + git_repos > 0 {
+                    layer.raw = raw;
+                    *changed = true;
+                    removed += sources + git_repos;
+                }
+            }
+
+            if removed == 0 {
+                let origin = layers
+                    .iter()
+                    .rev()
+                    .find(|l| {
+                        l.config.sources.iter().any(|s| s == path)
+                            || l.config.git_repos.iter().any(|r| r.path == *path)
+                    })
+                    .map_or(config.file.clone(), |l| l.path.clone());
+                return Err(Error::Config {
+                    path: origin,
+                    msg: format!("`{}` is listed, but couldn't be removed", path.display()),
+                }
+                .into());
+            }
+        }
+
+        for path in paths {
+            let mut deleted = false;
+            if self.delete {
+                let dst = config.dots_path(path)?;
+                if dst.exists() {
+                    trash::delete(&dst)?;
+                    deleted = true;
+                }
+            }
+
+            log::info!(
+                "➖ Removed `{}`",
+                console::style(path.display()).red().bold()
+            );
+            report.push(Action::Removed {
+                path: path.to_path_buf(),
+                deleted,
+            });
+        }
+
+        for (layer, _) in layers.iter().zip(changed).filter(|(_, changed)| *changed) {
+            log::trace!("Writing `{}`...", layer.path.display());
+            std::fs::write(&layer.path, &layer.raw)
+                .with_context(|| format!("Failed to write `{}`", layer.path.display()))?;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Let the user confirm deleting copies of `paths`, some of which were
+/// matched by keys that aren't exact, see `Matcher::is_exact`.
+///
+/// * `paths`: &[&Path]
+fn confirm_delete(paths: &[&Path]) -> anyhow::Result<()> {
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| format!("`{}`", path.display()))
+        .collect();
+
+    if !console::user_attended_stderr() {
+        return Err(Error::Aborted(format!(
+            "not deleting {}, as keys weren't exact, pass `--assumeyes` to anyway",
+            paths.join(", ")
+        ))
+        .into());
+    }
+
+    let confirmed = dialoguer::Confirm::new()
+        .with_prompt(format!(
+            " ~ {} {}. Continue?",
+            console::style("Deleting").yellow().bold(),
+            paths.join(", ")
+        ))
+        .interact()
+        .map_err(|err| Error::Aborted(err.to_string()))?;
+
+    match confirmed {
+        true => Ok(()),
+        false => Err(Error::Aborted("not confirmed".to_string()).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RemoveCmd;
+    use crate::{
+        cmd::RunWith,
+        errors::{exit, exit_code},
+        models::{config, report::Action},
+    };
+
+    #[test]
+    fn included_entries_are_removed_from_their_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (team, nedots) = (tmp.path().join("team.yml"), tmp.path().join("nedots.yml"));
+        let mine = "include: [team.yml]\nremote: x\nsources:\n  - .profile\n";
+        std::fs::write(
+            &team,
+            "sources:\n  # Shared\n  - .config/a\n  - .config/b\n",
+        )
+        .unwrap();
+        std::fs::write(&nedots, mine).unwrap();
+
+        let remove = RemoveCmd {
+            keys: vec![".config/a".into()],
+            delete: false,
+            assumeyes: false,
+        };
+        let report = remove.run_with(&config::read(&nedots).unwrap()).unwrap();
+
+        assert!(matches!(
+            &report.actions[..],
+            [Action::Removed { deleted: false, .. }]
+        ));
+        assert_eq!(
+            std::fs::read_to_string(&team).unwrap(),
+            "sources:\n  # Shared\n  - .config/b\n"
+        );
+        assert_eq!(std::fs::read_to_string(&nedots).unwrap(), mine);
+    }
+
+    #[test]
+    fn inexact_keys_arent_deleted_unconfirmed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let nedots = tmp.path().join("nedots.yml");
+        let raw = format!(
+            "remote: x\ndots_dir: {}\nsources: [.config/nvim]\n",
+            tmp.path().join("dots").display()
+        );
+        std::fs::write(&nedots, &raw).unwrap();
+        let config = config::read(&nedots).unwrap();
+
+        let mut remove = RemoveCmd {
+            keys: vec!["nvim".into()],
+            delete: true,
+            assumeyes: false,
+        };
+        // Attended, this would prompt instead.
+        if !console::user_attended_stderr() {
+            let err = remove.run_with(&config).unwrap_err();
+            assert_eq!(exit_code(&err), exit::ABORTED);
+            assert_eq!(std::fs::read_to_string(&nedots).unwrap(), raw);
+        }
+
+        remove.assumeyes = true;
+        let report = remove.run_with(&config).unwrap();
+        assert!(matches!(&report.actions[..], [Action::Removed { .. }]));
+    }
+}
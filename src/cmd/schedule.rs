@@ -0,0 +1,587 @@
+//! Run `nedots sync --gather` periodically, with a systemd user timer, or a
+//! crontab entry where systemd isn't running.
+
+use crate::{
+    models::{
+        config::Config,
+        report::{Action, Report},
+    },
+    utils::{self, paths::MakeDirs},
+    RootCmd,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+/// Name of the systemd user service & timer.
+const UNIT: &str = "nedots-sync";
+/// Marks the crontab entry as ours.
+const CRON_MARKER: &str = "# nedots-sync";
+
+#[derive(Debug, clap::Args)]
+pub struct ScheduleCmd {
+    #[command(subcommand)]
+    cmd: ScheduleSubCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum ScheduleSubCommand {
+    /// Sync periodically
+    Enable {
+        /// How often to sync, e.g. `30m`, `1h` or `1d`
+        #[arg(short, long, default_value = "1h", value_parser = parse_every)]
+        every: Duration,
+
+        /// [default: systemd, if it's running, otherwise cron]
+        #[arg(short, long, value_enum)]
+        backend: Option<Backend>,
+    },
+    /// Stop syncing periodically
+    Disable,
+    /// Show whether syncing periodically, & how
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum Backend {
+    Systemd,
+    Cron,
+}
+
+impl Backend {
+    /// systemd if it's running, i.e. the system was booted with it.
+    fn detect() -> Backend {
+        match Path::new("/run/systemd/system").is_dir() && utils::is_on_path("systemctl") {
+            true => Backend::Systemd,
+            false => Backend::Cron,
+        }
+    }
+}
+
+impl super::ValidateConfig for ScheduleCmd {
+    /// `ScheduleCmd` doesn't touch `sources`, they needn't exist.
+    ///
+    /// * `config`: mut Config
+    fn validate(&self, mut config: Config, _: &RootCmd) -> anyhow::Result<Config> {
+        config = config.resolve_dirs();
+        log::debug!("Resolved {:#?}", config);
+        Ok(config)
+    }
+}
+
+impl super::RunWith<RootCmd> for ScheduleCmd {
+    /// `ScheduleCmd` forwards the flags of `root_args` to the command that's
+    /// scheduled, so it's initialized with a `Config` here.
+    ///
+    /// * `root_args`: &RootCmd
+    fn run_with(&self, root_args: &RootCmd) -> anyhow::Result<Report> {
+        let config = &super::Initialize::init(self, root_args)?;
+        match &self.cmd {
+            ScheduleSubCommand::Enable { every, backend } => {
+                #[derive(serde::Serialize)]
+                struct Enabled {
+                    backend: Backend,
+                    every: String,
+                }
+
+                let args = sync_args(config, root_args)?;
+                let backend = backend.unwrap_or_else(Backend::detect);
+                let written = match backend {
+                    Backend::Systemd => enable_systemd(&args, *every)?,
+                    Backend::Cron => {
+                        enable_cron(&args, *every)?;
+                        Vec::new()
+                    }
+                };
+
+                log::info!(
+                    "✅ {} every {}",
+                    console::style("Syncing").bold(),
+                    console::style(describe(*every)).green()
+                );
+
+                let mut report = Report::with_data(&Enabled {
+                    backend,
+                    every: describe(*every),
+                })?;
+                for path in written {
+                    report.push(Action::Written { path });
+                }
+                Ok(report)
+            }
+            ScheduleSubCommand::Disable => disable(),
+            ScheduleSubCommand::Status => status(),
+        }
+    }
+}
+
+/// How often to sync, see `utils::parse_duration`. Neither systemd nor cron
+/// run anything more often than every minute.
+///
+/// * `raw`: &str
+fn parse_every(raw: &str) -> anyhow::Result<Duration> {
+    let every = utils::parse_duration(raw)?;
+    if every < Duration::from_secs(60) || every.subsec_nanos() > 0 {
+        anyhow::bail!(
+            "Can't sync every `{}`, use whole seconds of 1m or more",
+            raw
+        );
+    }
+
+    Ok(every)
+}
+
+/// The command that's scheduled, with `nedots.yml` & directories as they're
+/// used now. Each flag of `root_args` that's set is forwarded, with paths
+/// resolved, since the scheduled command runs elsewhere.
+///
+/// * `config`: &Config
+/// * `root_args`: &RootCmd
+fn sync_args(config: &Config, root_args: &RootCmd) -> anyhow::Result<Vec<String>> {
+    let exe = std::env::current_exe()?;
+    let mut args = vec![
+        exe.display().to_string(),
+        "--config".to_string(),
+        config.file.display().to_string(),
+    ];
+
+    for (flag, set, path) in [
+        ("--root", root_args.root.is_some(), &config.root),
+        ("--dots-dir", root_args.dots_dir.is_some(), &config.dots_dir),
+        (
+            "--backup-dir",
+            root_args.backup_dir.is_some(),
+            &config.backup_dir,
+        ),
+    ] {
+        if set {
+            args.extend([flag.to_string(), path.display().to_string()]);
+        }
+    }
+
+    if root_args.repo_config {
+        args.push("--repo-config".to_string());
+    }
+    if root_args.lenient {
+        args.push("--lenient".to_string());
+    }
+
+    args.extend(["sync", "--gather"].map(String::from));
+    Ok(args)
+}
+
+/// E.g. `1h` or `90s`, the largest unit that's exact.
+///
+/// * `every`: Duration
+fn describe(every: Duration) -> String {
+    let secs = every.as_secs();
+    [("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60)]
+        .into_iter()
+        .find(|(_, unit)| secs >= *unit && secs.is_multiple_of(*unit))
+        .map(|(name, unit)| format!("{}{}", secs / unit, name))
+        .unwrap_or_else(|| format!("{}s", secs))
+}
+
+/// `$XDG_CONFIG_HOME/systemd/user`, where user units are kept.
+fn units_dir() -> PathBuf {
+    directories::BaseDirs::new()
+        .expect("No BaseDirs")
+        .config_dir()
+        .join("systemd/user")
+}
+
+/// Quote `arg` for `ExecStart`, if it needs it.
+///
+/// * `arg`: &str
+fn systemd_quote(arg: &str) -> String {
+    match arg.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '%')) {
+        true => format!(
+            "\"{}\"",
+            arg.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('%', "%%")
+        ),
+        false => arg.to_string(),
+    }
+}
+
+/// * `args`: &[String], see `sync_args`.
+fn service_unit(args: &[String]) -> String {
+    let exec: Vec<String> = args.iter().map(|a| systemd_quote(a)).collect();
+    format!(
+        "[Unit]
+Description=Sync dotfiles with nedots
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=oneshot
+ExecStart={}
+",
+        exec.join(" ")
+    )
+}
+
+/// * `every`: Duration
+fn timer_unit(every: Duration) -> String {
+    format!(
+        "[Unit]
+Description=Sync dotfiles with nedots every {every}
+
+[Timer]
+OnBootSec={every}
+OnUnitActiveSec={every}
+
+[Install]
+WantedBy=timers.target
+",
+        every = describe(every)
+    )
+}
+
+/// Write the service & timer, then start the timer. Returns the units
+/// written.
+///
+/// * `args`: &[String], see `sync_args`.
+/// * `every`: Duration
+fn enable_systemd(args: &[String], every: Duration) -> anyhow::Result<Vec<PathBuf>> {
+    let dir = units_dir();
+    dir.make_all_dirs()?;
+
+    let mut written = Vec::new();
+    for (path, unit) in [
+        (dir.join(format!("{}.service", UNIT)), service_unit(args)),
+        (dir.join(format!("{}.timer", UNIT)), timer_unit(every)),
+    ] {
+        log::trace!("Writing `{}`...", path.display());
+        std::fs::write(&path, unit)?;
+        written.push(path);
+    }
+
+    let timer = format!("{}.timer", UNIT);
+    utils::run_cmd("systemctl", &["--user", "daemon-reload"])?;
+    utils::run_cmd("systemctl", &["--user", "enable", "--now", &timer])?;
+    Ok(written)
+}
+
+/// Cron schedule of `every`, which must be a whole number of minutes, hours
+/// or days that cron can express.
+///
+/// * `every`: Duration
+fn cron_schedule(every: Duration) -> anyhow::Result<String> {
+    let mins = every.as_secs() / 60;
+    if !every.as_secs().is_multiple_of(60) || mins == 0 {
+        anyhow::bail!(
+            "cron can't sync every {}, use whole minutes",
+            describe(every)
+        );
+    }
+
+    Ok(match mins {
+        1 => "* * * * *".to_string(),
+        60 => "0 * * * *".to_string(),
+        1440 => "0 0 * * *".to_string(),
+        m if m < 60 && 60_u64.is_multiple_of(m) => format!("*/{} * * * *", m),
+        m if m < 1440 && m.is_multiple_of(60) && 24_u64.is_multiple_of(m / 60) => {
+            format!("0 */{} * * *", m / 60)
+        }
+        m if m.is_multiple_of(1440) => format!("0 0 */{} * *", m / 1440),
+        _ => anyhow::bail!(
+            "cron can't sync every {}, use minutes or hours that divide evenly",
+            describe(every)
+        ),
+    })
+}
+
+/// Quote `arg` for `sh`, if it needs it. `%` is escaped, as cron treats it as
+/// a newline.
+///
+/// * `arg`: &str
+fn sh_quote(arg: &str) -> String {
+    let arg = arg.replace('%', "\\%");
+    match arg.contains(|c: char| !(c.is_ascii_alphanumeric() || "/-_.=\\%".contains(c))) {
+        true => format!("'{}'", arg.replace('\'', "'\\''")),
+        false => arg,
+    }
+}
+
+/// Our crontab entry.
+///
+/// * `args`: &[String], see `sync_args`.
+/// * `every`: Duration
+fn cron_entry(args: &[String], every: Duration) -> anyhow::Result<String> {
+    let cmd: Vec<String> = args.iter().map(|a| sh_quote(a)).collect();
+    Ok(format!(
+        "{} {} {}",
+        cron_schedule(every)?,
+        cmd.join(" "),
+        CRON_MARKER
+    ))
+}
+
+/// `crontab` without our entry, & with `entry` if given.
+///
+/// * `crontab`: &str
+/// * `entry`: Option<&str>
+fn edit_crontab(crontab: &str, entry: Option<&str>) -> String {
+    crontab
+        .lines()
+        .filter(|line| !line.ends_with(CRON_MARKER))
+        .chain(entry)
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// The user's crontab, empty if they've none.
+fn read_crontab() -> anyhow::Result<String> {
+    let output = Command::new("crontab").arg("-l").output()?;
+    match output.status.success() {
+        true => Ok(String::from_utf8(output.stdout)?),
+        false => Ok(String::new()),
+    }
+}
+
+/// * `crontab`: &str
+fn write_crontab(crontab: &str) -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("nedots-crontab-{}", std::process::id()));
+    std::fs::write(&path, crontab)?;
+    let res = utils::run_cmd("crontab", &[&path.display().to_string()]);
+    std::fs::remove_file(&path)?;
+    res
+}
+
+/// Replace our crontab entry, if any.
+///
+/// * `args`: &[String], see `sync_args`.
+/// * `every`: Duration
+fn enable_cron(args: &[String], every: Duration) -> anyhow::Result<()> {
+    let entry = cron_entry(args, every)?;
+    write_crontab(&edit_crontab(&read_crontab()?, Some(&entry)))
+}
+
+/// Stop & remove the systemd timer & service, & our crontab entry, whichever
+/// are present.
+fn disable() -> anyhow::Result<Report> {
+    #[derive(serde::Serialize)]
+    struct Disabled {
+        disabled: bool,
+    }
+
+    let mut disabled = false;
+
+    let service = units_dir().join(format!("{}.service", UNIT));
+    let timer = units_dir().join(format!("{}.timer", UNIT));
+    if timer.exists() || service.exists() {
+        let unit = format!("{}.timer", UNIT);
+        if let Err(err) = utils::run_cmd("systemctl", &["--user", "disable", "--now", &unit]) {
+            log::warn!("⚠️ Couldn't stop {} ({})", unit, err);
+        }
+
+        for path in [&timer, &service] {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        utils::run_cmd("systemctl", &["--user", "daemon-reload"])?;
+        disabled = true;
+    }
+
+    if utils::is_on_path("crontab") {
+        let crontab = read_crontab()?;
+        let edited = edit_crontab(&crontab, None);
+        if edited != crontab {
+            write_crontab(&edited)?;
+            disabled = true;
+        }
+    }
+
+    match disabled {
+        true => log::info!("✅ {}", console::style("Stopped syncing").bold()),
+        false => log::info!("👍 Nothing was scheduled"),
+    }
+    Report::with_data(&Disabled { disabled })
+}
+
+/// What's scheduled, as `data` of `status`.
+#[derive(Debug, Default, serde::Serialize)]
+struct Status {
+    systemd: Option<SystemdStatus>,
+    /// Our crontab entries.
+    cron: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct SystemdStatus {
+    timer: PathBuf,
+    /// E.g. `active` or `inactive`.
+    active: String,
+    /// When the timer next elapses, if it's active.
+    next: String,
+}
+
+fn status() -> anyhow::Result<Report> {
+    let timer = units_dir().join(format!("{}.timer", UNIT));
+    let mut status = Status::default();
+
+    if timer.exists() {
+        let unit = format!("{}.timer", UNIT);
+        let active = utils::run_cmd_output("systemctl", &["--user", "is-active", &unit])
+            .unwrap_or_else(|_| "inactive".to_string());
+        let next = utils::run_cmd_output(
+            "systemctl",
+            &[
+                "--user",
+                "show",
+                "--value",
+                "-p",
+                "NextElapseUSecRealtime",
+                &unit,
+            ],
+        )
+        .unwrap_or_default();
+
+        log::info!(
+            "⏲️ {} {} {}",
+            console::style("systemd").bold(),
+            console::style(timer.display()).blue(),
+            active
+        );
+        if !next.is_empty() {
+            log::info!("   next sync {}", next);
+        }
+        status.systemd = Some(SystemdStatus {
+            timer,
+            active,
+            next,
+        });
+    }
+
+    if utils::is_on_path("crontab") {
+        for line in read_crontab()?.lines().filter(|l| l.ends_with(CRON_MARKER)) {
+            log::info!(
+                "⏲️ {} {}",
+                console::style("cron").bold(),
+                console::style(line).blue()
+            );
+            status.cron.push(line.to_string());
+        }
+    }
+
+    if status.systemd.is_none() && status.cron.is_empty() {
+        log::info!("💤 Not scheduled, see `nedots schedule enable`");
+    }
+    Report::with_data(&status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cron_entry, edit_crontab, parse_every, service_unit, sync_args, timer_unit};
+    use crate::{models::config::Config, RootCmd};
+    use clap::Parser;
+    use std::time::Duration;
+
+    fn args() -> Vec<String> {
+        [
+            "/usr/bin/nedots",
+            "--config",
+            "/home/me/my dots/nedots.yml",
+            "sync",
+            "--gather",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    #[test]
+    fn systemd_units_are_generated() {
+        assert_eq!(
+            service_unit(&args()),
+            "[Unit]
+Description=Sync dotfiles with nedots
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=oneshot
+ExecStart=/usr/bin/nedots --config \"/home/me/my dots/nedots.yml\" sync --gather
+"
+        );
+
+        assert_eq!(
+            timer_unit(Duration::from_secs(90 * 60)),
+            "[Unit]
+Description=Sync dotfiles with nedots every 90m
+
+[Timer]
+OnBootSec=90m
+OnUnitActiveSec=90m
+
+[Install]
+WantedBy=timers.target
+"
+        );
+    }
+
+    #[test]
+    fn crontab_entry_is_replaced() {
+        let entry = cron_entry(&args(), Duration::from_secs(2 * 60 * 60)).unwrap();
+        assert_eq!(
+            entry,
+            "0 */2 * * * /usr/bin/nedots --config '/home/me/my dots/nedots.yml' sync --gather \
+            # nedots-sync"
+        );
+
+        let every = |secs| cron_entry(&args(), Duration::from_secs(secs));
+        assert!(every(15 * 60).unwrap().starts_with("*/15 * * * * "));
+        assert!(every(24 * 60 * 60).unwrap().starts_with("0 0 * * * "));
+        assert!(every(90 * 60).is_err());
+        assert!(every(30).is_err());
+
+        let crontab = "MAILTO=me\n0 * * * * old # nedots-sync\n";
+        assert_eq!(
+            edit_crontab(crontab, Some(&entry)),
+            format!("MAILTO=me\n{}\n", entry)
+        );
+        assert_eq!(edit_crontab(crontab, None), "MAILTO=me\n");
+    }
+
+    #[test]
+    fn flags_that_are_set_are_forwarded() {
+        let config = Config {
+            file: "/home/me/nedots.yml".into(),
+            root: "/data/dots".into(),
+            dots_dir: "/data/dots/dots".into(),
+            backup_dir: "/data/dots/backups".into(),
+            ..Default::default()
+        };
+        let root_args = RootCmd::parse_from(["nedots", "--root", "dots", "--repo-config"]);
+
+        let args = sync_args(&config, &root_args).unwrap();
+        assert_eq!(
+            args[1..],
+            [
+                "--config",
+                "/home/me/nedots.yml",
+                "--root",
+                "/data/dots",
+                "--repo-config",
+                "sync",
+                "--gather"
+            ]
+        );
+    }
+
+    #[test]
+    fn sub_minute_intervals_are_rejected() {
+        assert_eq!(parse_every("90s").unwrap(), Duration::from_secs(90));
+        assert!(parse_every("500ms").is_err());
+        assert!(parse_every("59s").is_err());
+        assert!(parse_every("60500ms").is_err());
+
+        // Too many seconds for a u64 is an error, not an overflow.
+        assert!(parse_every("999999999999999999d").is_err());
+    }
+}
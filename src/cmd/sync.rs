@@ -1,7 +1,14 @@
 use crate::{
-    models::{config::Config, git_repo::GitRepo},
+    models::{
+        config::Config,
+        git_repo::{GitRepo, RepoStatus},
+        hooks::{Env, Event},
+        report::{Action, Report},
+        state::State,
+    },
     utils::spinner::Spinner,
 };
+use std::path::Path;
 
 #[derive(Debug, clap::Parser)]
 pub(crate) struct SyncCmd {
@@ -12,61 +19,501 @@ pub(crate) struct SyncCmd {
     /// Don't push to remote, useful for testing
     #[arg(short, long)]
     nopush: bool,
+
+    /// Fetch the complete history of shallow clones before syncing
+    #[arg(long)]
+    unshallow: bool,
+
+    /// Stop at the first source that fails to gather, or submodule that fails
+    /// to update. Repositories are synced concurrently, so each is attempted
+    #[arg(long)]
+    fail_fast: bool,
+}
+
+/// What happened to a `GitRepo` during `sync`.
+enum SyncOutcome {
+    /// Nothing to do, `RepoStatus` was up to date.
+    Skipped(RepoStatus),
+    /// `RepoStatus` prior to syncing.
+    Synced(RepoStatus),
+    /// Remote was unreachable, so changes were only committed locally.
+    Offline(RepoStatus),
+}
+
+impl SyncOutcome {
+    /// Whether there are local commits that didn't make it to remote.
+    ///
+    /// * `push`: bool, if we weren't pushing, nothing is pending.
+    fn is_push_pending(&self, push: bool) -> bool {
+        match self {
+            SyncOutcome::Offline(status) => push && (status.dirty || status.has_unpushed()),
+            _ => false,
+        }
+    }
+
+    /// Whether anything was committed, pulled or pushed.
+    fn is_changed(&self) -> bool {
+        match self {
+            SyncOutcome::Skipped(_) => false,
+            SyncOutcome::Synced(_) => true,
+            SyncOutcome::Offline(status) => status.dirty,
+        }
+    }
+
+    fn status(&self) -> &RepoStatus {
+        match self {
+            SyncOutcome::Skipped(status)
+            | SyncOutcome::Synced(status)
+            | SyncOutcome::Offline(status) => status,
+        }
+    }
+
+    /// E.g. `synced`, or `offline, push pending`.
+    ///
+    /// * `push`: bool, see `is_push_pending`.
+    fn result(&self, push: bool) -> &'static str {
+        match self {
+            SyncOutcome::Skipped(_) => "skipped",
+            SyncOutcome::Synced(_) => "synced",
+            SyncOutcome::Offline(_) if self.is_push_pending(push) => "offline, push pending",
+            SyncOutcome::Offline(_) => "offline",
+        }
+    }
+
+    /// * `repo`: &GitRepo, that was synced.
+    /// * `push`: bool
+    fn action(&self, repo: &GitRepo, push: bool) -> Action {
+        match self {
+            SyncOutcome::Skipped(_) => Action::UpToDate {
+                remote: repo.remote.clone(),
+                path: repo.path.clone(),
+            },
+            _ => Action::Synced {
+                path: repo.path.clone(),
+                status: self.status().to_string(),
+                result: self.result(push).to_string(),
+            },
+        }
+    }
 }
 
 impl super::RunWith<Config> for SyncCmd {
-    fn run_with(&self, config: &Config) -> anyhow::Result<()> {
-        fn git_add_commit_push(repo: &GitRepo, push: bool) -> anyhow::Result<()> {
-            let spinner = Spinner::start();
-
-            spinner.set_msg(&format!(
-                " Adding latest changes... {}",
-                console::style(repo.path.display()).blue()
-            ));
-            repo.add(".")?;
-
-            spinner.set_msg(&format!(
-                " Commiting latest changes... {}",
-                console::style(repo.path.display()).blue()
-            ));
-            repo.commit(&format!("Latest {}", chrono::offset::Local::now()))?;
-
-            spinner.set_msg(&format!(
-                " Pulling latest changes... {}",
-                console::style(&repo.remote).blue()
-            ));
-            repo.pull()?;
-
-            if push {
-                spinner.set_msg(&format!(
-                    " Pushing to remote... {}",
-                    console::style(&repo.remote).blue()
-                ));
-                repo.push()?;
-            }
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let env = config.hook_env();
+        let mut report = Report::default();
+        config
+            .hooks
+            .around(Event::Sync, &env, || self.sync(config, &env, &mut report))?;
+
+        log::info!("✅ {}", console::style("Synced!").bold());
+        Ok(report)
+    }
+}
 
-            spinner.finish();
-            Ok(())
+impl SyncCmd {
+    /// * `gather`: bool
+    /// * `nopush`: bool
+    pub(crate) fn new(gather: bool, nopush: bool) -> SyncCmd {
+        SyncCmd {
+            gather,
+            nopush,
+            unshallow: false,
+            fail_fast: false,
         }
+    }
+
+    /// Returns whether any repository changed.
+    ///
+    /// * `config`: &Config
+    /// * `env`: &Env, of hooks.
+    /// * `report`: &mut Report, of each source gathered & repository synced.
+    pub(crate) fn sync(
+        &self,
+        config: &Config,
+        env: &Env,
+        report: &mut Report,
+    ) -> anyhow::Result<bool> {
+        let mut repos: Vec<GitRepo> = Vec::new();
 
         if self.gather {
-            let spinner = Spinner::start();
-            spinner.set_msg(" Gathering source files & directories...");
+            config.hooks.around(Event::Gather, env, || {
+                gather(config, report, self.fail_fast)
+            })?;
+            repos.extend(config.git_repos.iter().cloned());
+        }
 
-            for source in &config.sources {
-                let dst = crate::utils::join_paths(&config.dots_dir, source);
-                crate::ops::copy(source, &dst)?;
+        // Retry pushes that failed last time because we were offline, even
+        // if we haven't been asked to sync those repositories.
+        let state_path = State::path(&config.root);
+        let mut state = State::read(&state_path)?;
+        for path in &state.pending_push {
+            if let Some(repo) = config.git_repos.iter().find(|r| &r.path == path) {
+                if !repos.iter().any(|r| r.path == repo.path) {
+                    log::debug!("Retrying push of {}", path.display());
+                    repos.push(Clone::clone(repo));
+                }
             }
-            spinner.finish();
+        }
+
+        let root = config.root_repo();
 
-            for repo in &config.git_repos {
-                git_add_commit_push(repo, !self.nopush)?;
+        let spinner = Spinner::start();
+        spinner.set_msg(&format!(" Syncing {} repositories...", repos.len() + 1));
+
+        let mut outcomes = Vec::new();
+        if config.submodules {
+            // The root repository records the commits of `git_repos`, so it
+            // must wait for them to finish before bumping submodule pointers.
+            outcomes = sync_all(&repos, self, env);
+
+            spinner.set_msg(" Updating submodules...");
+            for (repo, outcome) in repos.iter().zip(&outcomes) {
+                if outcome.is_ok() {
+                    let path = config.submodule_path(repo);
+                    if let Err(err) = bump_submodule(&root, repo, &path) {
+                        report.record(&root.path.join(path), Err(err), self.fail_fast)?;
+                    }
+                }
             }
         }
 
-        git_add_commit_push(&GitRepo::new(&config.remote, &config.root), !self.nopush)?;
+        // Anything not yet synced is independent of one another.
+        repos.push(root);
+        outcomes.extend(sync_all(&repos[outcomes.len()..], self, env));
+        spinner.finish();
 
-        log::info!("✅ {}", console::style("Synced!").bold());
-        Ok(())
+        if !self.nopush {
+            for (repo, outcome) in repos.iter().zip(&outcomes) {
+                match outcome {
+                    Ok(outcome) if outcome.is_push_pending(true) => {
+                        state.add_pending_push(&repo.path)
+                    }
+                    Ok(_) => state.remove_pending_push(&repo.path),
+                    Err(_) => (),
+                }
+            }
+            state.write(&state_path)?;
+        }
+
+        print_outcomes(&repos, &outcomes, !self.nopush);
+        for (repo, outcome) in repos.iter().zip(&outcomes) {
+            report.push(match outcome {
+                Ok(outcome) => outcome.action(repo, !self.nopush),
+                Err(err) => Action::failed(&repo.path, err),
+            });
+        }
+
+        report.check(match self.gather {
+            true => "sources & repositories",
+            false => "repositories",
+        })?;
+
+        Ok(outcomes.iter().flatten().any(SyncOutcome::is_changed))
+    }
+}
+
+/// Commit everything in the root repository locally, `nedots.yml` included,
+/// without pulling or pushing. Returns `Committed`, if anything was.
+///
+/// * `config`: &Config
+pub(crate) fn commit_root(config: &Config) -> anyhow::Result<Option<Action>> {
+    let root = config.root_repo();
+    if !root.is_changed(Path::new(".")) {
+        return Ok(None);
+    }
+
+    commit_latest(&root)?;
+    Ok(Some(Action::Committed { path: root.path }))
+}
+
+/// Commit everything in `repo`, as of now.
+///
+/// * `repo`: &GitRepo
+fn commit_latest(repo: &GitRepo) -> anyhow::Result<()> {
+    repo.add(".")?;
+    repo.commit(&format!("Latest {}", chrono::offset::Local::now()))
+}
+
+/// `ops::gather`, with a spinner.
+///
+/// * `config`: &Config
+/// * `report`: &mut Report
+/// * `fail_fast`: bool
+fn gather(config: &Config, report: &mut Report, fail_fast: bool) -> anyhow::Result<bool> {
+    let spinner = Spinner::start();
+    spinner.set_msg(" Gathering source files & directories...");
+    let changed = crate::ops::gather(config, report, fail_fast)?;
+    spinner.finish();
+    Ok(changed)
+}
+
+/// Sync each of `repos` on its own thread, since they're independent of one
+/// another. Outcomes are returned in the same order as `repos`.
+///
+/// * `repos`: &[GitRepo]
+/// * `args`: &SyncCmd
+/// * `env`: &Env, of hooks.
+fn sync_all(repos: &[GitRepo], args: &SyncCmd, env: &Env) -> Vec<anyhow::Result<SyncOutcome>> {
+    std::thread::scope(|scope| {
+        repos
+            .iter()
+            .map(|repo| scope.spawn(move || sync_with_hooks(repo, args, env)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("Sync thread panicked")))
+            })
+            .collect()
+    })
+}
+
+/// `sync_repo`, between the hooks of `repo`.
+///
+/// * `repo`: &GitRepo
+/// * `args`: &SyncCmd
+/// * `env`: &Env
+fn sync_with_hooks(repo: &GitRepo, args: &SyncCmd, env: &Env) -> anyhow::Result<SyncOutcome> {
+    let mut env = env.clone();
+    env.push(("NEDOTS_SRC", repo.remote.clone().into()));
+    env.push(("NEDOTS_DST", repo.path.clone().into()));
+
+    repo.hooks.pre(Event::Sync, &env)?;
+    let outcome = sync_repo(repo, args)?;
+    repo.hooks.post(Event::Sync, outcome.is_changed(), &env)?;
+    Ok(outcome)
+}
+
+/// Check `RepoStatus` first, and only add, commit, pull & push if required.
+/// If remote is unreachable, changes are committed but not pulled or pushed.
+///
+/// * `repo`: &GitRepo
+/// * `args`: &SyncCmd
+fn sync_repo(repo: &GitRepo, args: &SyncCmd) -> anyhow::Result<SyncOutcome> {
+    /// Let unreachable remotes through, we'll try again next time.
+    fn offline_ok(res: anyhow::Result<()>) -> anyhow::Result<bool> {
+        match res {
+            Ok(_) => Ok(true),
+            Err(err) if GitRepo::is_unreachable(&err) => {
+                log::warn!("{}", err);
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    if args.unshallow && repo.is_shallow() {
+        log::trace!("Unshallowing `{}`...", repo.path.display());
+        repo.unshallow()?;
+    }
+
+    if !repo.mirrors.is_empty() {
+        repo.add_mirrors()?;
+    }
+
+    let push = !args.nopush;
+    let online = offline_ok(repo.fetch())?;
+    let status = repo.status()?;
+    log::debug!("{} is {}", repo.path.display(), status);
+
+    if online && !status.needs_sync(push) {
+        return Ok(SyncOutcome::Skipped(status));
+    }
+
+    if status.dirty {
+        commit_latest(repo)?;
+    }
+
+    // Without `remote`, we don't know if we're behind, but a mirror might.
+    let pull = match online {
+        true => status.behind > 0,
+        false => !repo.mirrors.is_empty(),
+    };
+
+    if (!online && !pull) || (pull && !offline_ok(repo.pull())?) {
+        return Ok(SyncOutcome::Offline(status));
+    }
+
+    if push && (status.dirty || status.has_unpushed()) && !offline_ok(repo.push())? {
+        return Ok(SyncOutcome::Offline(status));
+    }
+
+    Ok(SyncOutcome::Synced(status))
+}
+
+/// Register `repo` as a submodule of `root` if it isn't already, then point
+/// the submodule @ `path` at the current `HEAD` of `repo`. The change is
+/// committed when `root` is synced.
+///
+/// * `root`: &GitRepo, the root repository.
+/// * `repo`: &GitRepo, a repository in `git_repos`.
+/// * `path`: &Path, path of submodule relative to `root`.
+fn bump_submodule(root: &GitRepo, repo: &GitRepo, path: &Path) -> anyhow::Result<()> {
+    if !root.has_submodule(path) {
+        log::trace!("Registering submodule `{}`...", path.display());
+        root.add_submodule(&repo.remote, path)?;
+    }
+
+    let submodule = GitRepo::new(&repo.remote, &root.path.join(path));
+    submodule.fetch_from(&repo.path)?;
+    submodule.checkout(&repo.head()?)
+}
+
+/// Log a summary table of each `GitRepo` & its `SyncOutcome`.
+///
+/// * `repos`: &[GitRepo]
+/// * `outcomes`: &[anyhow::Result<SyncOutcome>], in the same order as `repos`.
+/// * `push`: bool, whether or not we were pushing to remote.
+fn print_outcomes(repos: &[GitRepo], outcomes: &[anyhow::Result<SyncOutcome>], push: bool) {
+    let rows: Vec<(String, String, console::StyledObject<String>)> = repos
+        .iter()
+        .zip(outcomes)
+        .map(|(repo, outcome)| {
+            let (status, result) = match outcome {
+                Ok(outcome) => {
+                    let result = console::style(outcome.result(push).to_string());
+                    let result = match outcome {
+                        SyncOutcome::Skipped(_) => result.dim(),
+                        SyncOutcome::Synced(_) => result.green(),
+                        SyncOutcome::Offline(_) => result.yellow(),
+                    };
+                    (outcome.status().to_string(), result)
+                }
+                Err(err) => (
+                    "unknown".to_string(),
+                    console::style(format!("failed ({})", err)).red(),
+                ),
+            };
+            (repo.path.display().to_string(), status, result)
+        })
+        .collect();
+
+    let path_width = rows.iter().map(|r| r.0.len()).max().unwrap_or_default();
+    let status_width = rows.iter().map(|r| r.1.len()).max().unwrap_or_default();
+    for (path, status, result) in &rows {
+        log::info!(
+            "{}  {:<status_width$}  {}",
+            console::style(format!("{:<path_width$}", path)).blue(),
+            status,
+            result,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bump_submodule, commit_root, sync_repo, SyncCmd, SyncOutcome};
+    use crate::{
+        models::{config::Config, git_repo::GitRepo, report::Report, state::State},
+        utils::testing::{
+            add_submodule, bare_repo, commit_files, git, git_output, head, init_repo,
+        },
+    };
+    use std::path::Path;
+
+    /// Clone of a bare repository @ `dir/remote.git`, with an identity to
+    /// commit as.
+    ///
+    /// * `dir`: &Path
+    fn clone_remote(dir: &Path) -> GitRepo {
+        let remote = bare_repo(dir, "remote", &[("a.conf", "1")]);
+        let repo = GitRepo::new(&remote.display().to_string(), &dir.join("root"));
+        repo.clone().unwrap();
+        git(&repo.path, &["config", "user.name", "nedots"]);
+        git(&repo.path, &["config", "user.email", "nedots@localhost"]);
+        repo
+    }
+
+    #[test]
+    fn clean_repo_is_skipped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = clone_remote(tmp.path());
+        let before = head(&repo.path);
+
+        let outcome = sync_repo(&repo, &SyncCmd::new(false, false)).unwrap();
+        assert!(matches!(outcome, SyncOutcome::Skipped(_)));
+        assert_eq!(head(&repo.path), before);
+        assert_eq!(head(Path::new(&repo.remote)), before);
+    }
+
+    #[test]
+    fn offline_commits_are_pushed_once_remote_is_back() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = clone_remote(tmp.path());
+        let remote = repo.remote.clone();
+        let mut config = Config {
+            root: repo.path.clone(),
+            ..Default::default()
+        };
+        let sync = |config: &Config| {
+            let mut report = Report::default();
+            SyncCmd::new(false, false)
+                .sync(config, &config.hook_env(), &mut report)
+                .unwrap();
+            State::read(&State::path(&config.root))
+                .unwrap()
+                .pending_push
+        };
+
+        // `.invalid` never resolves, as though offline.
+        let offline = "https://nedots.invalid/remote.git";
+        git(&repo.path, &["remote", "set-url", "origin", offline]);
+        config.remote = offline.to_string();
+        std::fs::write(repo.path.join("a.conf"), "2").unwrap();
+
+        assert_eq!(sync(&config), vec![repo.path.clone()]);
+        assert!(!repo.is_changed(Path::new(".")));
+        assert_ne!(head(&repo.path), head(Path::new(&remote)));
+
+        git(&repo.path, &["remote", "set-url", "origin", &remote]);
+        config.remote = remote.clone();
+
+        assert!(sync(&config).is_empty());
+        assert_eq!(head(&repo.path), head(Path::new(&remote)));
+    }
+
+    #[test]
+    fn config_is_committed_with_the_rest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        init_repo(&root);
+        commit_files(&root, &[("nedots.yml", "remote: x\n")]);
+
+        std::fs::write(root.join("nedots.yml"), "remote: y\n").unwrap();
+        std::fs::create_dir_all(root.join("dots/HOME")).unwrap();
+        std::fs::write(root.join("dots/HOME/.bashrc"), "").unwrap();
+
+        let config = Config {
+            file: root.join("nedots.yml"),
+            root: root.clone(),
+            ..Default::default()
+        };
+        assert!(commit_root(&config).unwrap().is_some());
+
+        let changed = git_output(&root, &["show", "--name-only", "--format=", "HEAD"]);
+        assert_eq!(changed, "dots/HOME/.bashrc\nnedots.yml");
+        assert!(!GitRepo::new("", &root).is_changed(Path::new(".")));
+    }
+
+    #[test]
+    fn submodule_is_bumped_to_head() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let remote = bare_repo(dir, "app", &[("app.conf", "v1")]);
+        let path = Path::new("dots/HOME/app");
+
+        let root = GitRepo::new("", &dir.join("root"));
+        init_repo(&root.path);
+        add_submodule(&root.path, &remote, path);
+
+        let repo = GitRepo::new(&remote.display().to_string(), &dir.join("app"));
+        repo.clone().unwrap();
+        init_repo(&repo.path);
+        commit_files(&repo.path, &[("app.conf", "v2")]);
+
+        bump_submodule(&root, &repo, path).unwrap();
+        assert_eq!(head(&root.path.join(path)), head(&repo.path));
+        assert!(root.is_changed(path));
     }
 }
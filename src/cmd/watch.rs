@@ -0,0 +1,353 @@
+//! Gather `sources` into `dots_dir` as they change, rather than all at once
+//! with `sync --gather`.
+//!
+//! Directories are watched recursively, & the directory each source is in is
+//! watched too, so that sources which are created or deleted are noticed. A
+//! source that doesn't exist yet is watched for via the nearest directory that
+//! does. Files deleted within a source are removed from `dots_dir`, but a
+//! deleted source keeps its copy, as `install` would bring it back.
+//!
+//! As `watch` never finishes, with `--output json` a `Report` is printed for
+//! each batch of changes, & each periodic sync.
+
+use super::sync::{self, SyncCmd};
+use crate::{
+    models::{
+        config::Config,
+        hooks::Event,
+        report::{self, Action, Output, Report},
+    },
+    utils::{self, ignore::Ignore, paths::expand_path},
+    RootCmd,
+};
+use notify::{RecursiveMode, Watcher};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, clap::Args)]
+pub struct WatchCmd {
+    /// Wait for changes to settle this long before gathering, e.g. `500ms`
+    #[arg(short, long, default_value = "2s", value_parser = utils::parse_duration)]
+    debounce: Duration,
+
+    /// Commit gathered changes to the root repository
+    #[arg(short, long)]
+    commit: bool,
+
+    /// Also pull & push at most this often, e.g. `1h`, implies `--commit`
+    #[arg(short, long, value_parser = utils::parse_duration)]
+    push: Option<Duration>,
+}
+
+impl super::ValidateConfig for WatchCmd {
+    /// `sources` needn't exist, they're gathered once they're created.
+    ///
+    /// * `config`: mut Config
+    fn validate(&self, mut config: Config, _: &RootCmd) -> anyhow::Result<Config> {
+        config = config.resolve_dirs();
+        log::debug!("Resolved {:#?}", config);
+        Ok(config)
+    }
+}
+
+impl super::RunWith<Config> for WatchCmd {
+    fn run_with(&self, config: &Config) -> anyhow::Result<Report> {
+        let ignore = Ignore::new(&config.ignore)?;
+        let sources = expand_sources(config)?;
+
+        let (tx, rx) = mpsc::channel();
+        let mut debouncer = notify_debouncer_mini::new_debouncer(self.debounce, tx)?;
+        let mut watched = BTreeMap::new();
+        rewatch(debouncer.watcher(), &sources, &[], &mut watched);
+
+        log::info!(
+            "👀 {} {} sources, press Ctrl+C to stop",
+            console::style("Watching").bold(),
+            sources.len()
+        );
+
+        let mut last_push = Instant::now();
+        loop {
+            let received = match self.push {
+                Some(interval) => rx.recv_timeout(interval.saturating_sub(last_push.elapsed())),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match received {
+                Ok(Ok(events)) => {
+                    let paths: Vec<PathBuf> = events.into_iter().map(|e| e.path).collect();
+                    log::debug!("Changed {:#?}", paths);
+
+                    let res = self.on_change(config, &sources, &paths, &ignore);
+                    if let Err(err) = &res {
+                        log::error!("❌ {:#}", err);
+                    }
+                    if Output::is_json() {
+                        report::print_json(&res);
+                    }
+                    rewatch(debouncer.watcher(), &sources, &paths, &mut watched);
+                }
+                Ok(Err(err)) => log::error!("❌ {}", err),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("Watcher stopped"),
+            }
+
+            if self
+                .push
+                .is_some_and(|interval| last_push.elapsed() >= interval)
+            {
+                let env = config.hook_env();
+                let mut batch = Report::default();
+                let res = config
+                    .hooks
+                    .around(Event::Sync, &env, || {
+                        SyncCmd::new(false, false).sync(config, &env, &mut batch)
+                    })
+                    .map(|_| batch);
+
+                match &res {
+                    Ok(_) => log::info!("✅ {}", console::style("Synced!").bold()),
+                    Err(err) => log::error!("❌ {:#}", err),
+                }
+                if Output::is_json() {
+                    report::print_json(&res);
+                }
+                last_push = Instant::now();
+            }
+        }
+    }
+}
+
+impl WatchCmd {
+    /// Gather `paths`, between the global gather hooks, then commit them if
+    /// asked to. Returns what was done.
+    ///
+    /// * `config`: &Config
+    /// * `sources`: &[(PathBuf, PathBuf)], see `expand_sources`.
+    /// * `paths`: &[PathBuf], that changed.
+    /// * `ignore`: &Ignore
+    fn on_change(
+        &self,
+        config: &Config,
+        sources: &[(PathBuf, PathBuf)],
+        paths: &[PathBuf],
+        ignore: &Ignore,
+    ) -> anyhow::Result<Report> {
+        let env = config.hook_env();
+        let mut report = Report::default();
+        let changed = config.hooks.around(Event::Gather, &env, || {
+            gather_changes(config, sources, paths, ignore, &mut report)
+        })?;
+
+        if changed && (self.commit || self.push.is_some()) {
+            if let Some(committed) = sync::commit_root(config)? {
+                log::info!("📝 {}", console::style("Committed").bold());
+                report.push(committed);
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Each of `sources`, as it's listed & as an absolute path.
+///
+/// * `config`: &Config
+fn expand_sources(config: &Config) -> anyhow::Result<Vec<(PathBuf, PathBuf)>> {
+    config
+        .sources
+        .iter()
+        .map(|source| Ok((source.clone(), expand_path(source)?)))
+        .collect()
+}
+
+/// Copy those of `paths` that exist into `dots_dir`, & remove those that don't
+/// from it, between the hooks of each source they're in. Returns whether
+/// anything changed.
+///
+/// * `config`: &Config
+/// * `sources`: &[(PathBuf, PathBuf)], see `expand_sources`.
+/// * `paths`: &[PathBuf], absolute.
+/// * `ignore`: &Ignore
+/// * `report`: &mut Report, of each path copied or deleted.
+fn gather_changes(
+    config: &Config,
+    sources: &[(PathBuf, PathBuf)],
+    paths: &[PathBuf],
+    ignore: &Ignore,
+    report: &mut Report,
+) -> anyhow::Result<bool> {
+    let mut changed = false;
+    for (source, src) in sources {
+        let within: Vec<&PathBuf> = paths.iter().filter(|p| p.starts_with(src)).collect();
+        if within.is_empty() {
+            continue;
+        }
+
+        let dst = config.dots_path(source)?;
+        let mut env = config.hook_env();
+        env.push(("NEDOTS_SRC", src.clone().into()));
+        env.push(("NEDOTS_DST", dst.clone().into()));
+
+        changed |= config.hooks_of(source).around(Event::Gather, &env, || {
+            let mut changed = false;
+            for path in within {
+                let relative = path.strip_prefix(src)?;
+                if ignore.is_ignored(relative) {
+                    log::trace!("Ignoring `{}`", path.display());
+                    continue;
+                }
+
+                let to = match relative.as_os_str().is_empty() {
+                    true => dst.clone(),
+                    false => dst.join(relative),
+                };
+
+                if path.exists() {
+                    if crate::ops::copy_ignoring(path, &to, ignore)? {
+                        log::info!("📥 Gathered {}", console::style(path.display()).blue());
+                        report.push(Action::copy(path, &to, true));
+                        changed = true;
+                    }
+                } else if path == src {
+                    log::warn!(
+                        "⚠️ `{}` was deleted, its copy in `dots_dir` is kept",
+                        source.display()
+                    );
+                } else if let Ok(meta) = to.symlink_metadata() {
+                    match meta.is_dir() {
+                        true => std::fs::remove_dir_all(&to)?,
+                        false => std::fs::remove_file(&to)?,
+                    }
+                    log::info!("🗑️ Removed {}", console::style(to.display()).blue());
+                    report.push(Action::Deleted { path: to });
+                    changed = true;
+                }
+            }
+            Ok(changed)
+        })?;
+    }
+
+    Ok(changed)
+}
+
+/// Where to watch each of `sources`: directories recursively, plus the nearest
+/// existing directory each source is in, to notice it being created or
+/// deleted.
+///
+/// * `sources`: &[(PathBuf, PathBuf)], see `expand_sources`.
+fn targets(sources: &[(PathBuf, PathBuf)]) -> BTreeMap<PathBuf, RecursiveMode> {
+    let mut targets = BTreeMap::new();
+    for (_, src) in sources {
+        if src.is_dir() {
+            targets.insert(src.clone(), RecursiveMode::Recursive);
+        }
+
+        if let Some(parent) = src.ancestors().skip(1).find(|p| p.is_dir()) {
+            targets
+                .entry(parent.to_path_buf())
+                .or_insert(RecursiveMode::NonRecursive);
+        }
+    }
+
+    targets
+}
+
+/// Bring `watched` in line with `targets`, as sources come & go. Targets among
+/// `changed` are watched afresh, in case they were deleted & created again.
+/// Failures are logged, & retried next time.
+///
+/// * `watcher`: &mut dyn Watcher
+/// * `sources`: &[(PathBuf, PathBuf)], see `expand_sources`.
+/// * `changed`: &[PathBuf]
+/// * `watched`: &mut BTreeMap<PathBuf, RecursiveMode>
+fn rewatch(
+    watcher: &mut dyn Watcher,
+    sources: &[(PathBuf, PathBuf)],
+    changed: &[PathBuf],
+    watched: &mut BTreeMap<PathBuf, RecursiveMode>,
+) {
+    let targets = targets(sources);
+
+    watched.retain(|path, mode| {
+        let keep = targets.get(path) == Some(mode) && !changed.contains(path);
+        if !keep {
+            log::trace!("Unwatching `{}`...", path.display());
+            // The path may already be gone, along with its watch.
+            let _ = watcher.unwatch(path);
+        }
+        keep
+    });
+
+    for (path, mode) in targets {
+        if watched.contains_key(&path) {
+            continue;
+        }
+
+        log::trace!("Watching `{}` {:?}...", path.display(), mode);
+        match watcher.watch(&path, mode) {
+            Ok(_) => {
+                watched.insert(path, mode);
+            }
+            Err(err) => log::warn!("⚠️ Couldn't watch `{}` ({})", path.display(), err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gather_changes, targets};
+    use crate::{
+        models::{config::Config, report::Report},
+        utils::ignore::Ignore,
+    };
+    use notify::RecursiveMode;
+
+    #[test]
+    fn changes_are_gathered_and_deletions_mirrored() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let src = dir.join("home/.config/nvim");
+        std::fs::create_dir_all(src.join("lua")).unwrap();
+        std::fs::write(src.join("init.lua"), "-- init").unwrap();
+        std::fs::write(src.join("lua/plugins.lua"), "-- plugins").unwrap();
+        std::fs::write(src.join(".init.lua.swp"), "").unwrap();
+
+        let config = Config {
+            dots_dir: dir.join("dots"),
+            sources: vec![src.clone(), dir.join("home/.zshrc")],
+            ..Default::default()
+        };
+        let sources = super::expand_sources(&config).unwrap();
+        let ignore = Ignore::new(&["*.swp".to_string()]).unwrap();
+        let dst = config.dots_path(&src).unwrap();
+
+        let targets = targets(&sources);
+        assert_eq!(targets[&src], RecursiveMode::Recursive);
+        assert_eq!(targets[&dir.join("home")], RecursiveMode::NonRecursive);
+
+        let mut report = Report::default();
+        let mut gather = |changed: &[_]| {
+            gather_changes(&config, &sources, changed, &ignore, &mut report).unwrap()
+        };
+
+        let changed = [src.clone(), dir.join("home/.zshrc")];
+        assert!(gather(&changed));
+        assert!(dst.join("lua/plugins.lua").exists());
+        assert!(!dst.join(".init.lua.swp").exists());
+        assert!(!gather(&changed));
+
+        std::fs::remove_file(src.join("init.lua")).unwrap();
+        assert!(gather(&[src.join("init.lua"), dir.join("elsewhere")]));
+        assert!(!dst.join("init.lua").exists());
+
+        std::fs::remove_dir_all(&src).unwrap();
+        assert!(!gather(std::slice::from_ref(&src)));
+        assert_eq!(report.actions.len(), 2);
+        assert!(dst.join("lua/plugins.lua").exists());
+    }
+}
@@ -1,10 +1,76 @@
+use std::path::PathBuf;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("`{0}` failed! Review the output & try again")]
-    Command(String),
+    /// The user declined, or couldn't be asked, e.g. without a terminal.
+    #[error("Aborted ({0})")]
+    Aborted(String),
+
+    #[error("`{key}` is ambiguous, it could be any of: {candidates}")]
+    Ambiguous { key: String, candidates: String },
+
+    /// A program other than `git` exited unsuccessfully.
+    #[error("`{cmd}` failed{}! Review the output & try again", exited(code))]
+    Command {
+        cmd: String,
+        /// `None` if it was killed by a signal.
+        code: Option<i32>,
+        stderr: String,
+    },
+
+    /// `nedots.yml` parsed, but doesn't make sense.
+    #[error("Invalid config `{}`, {msg}", path.display())]
+    Config { path: PathBuf, msg: String },
+
+    #[error("Failed to copy `{}` -> `{}` ({err})", from.display(), to.display())]
+    Copy {
+        from: PathBuf,
+        to: PathBuf,
+        err: std::io::Error,
+    },
+
+    /// Some files within `from` failed to copy, the rest were copied.
+    #[error("{} files within `{}` failed to copy, e.g. {first}", others.len() + 1, from.display())]
+    CopyMany {
+        from: PathBuf,
+        first: Box<Error>,
+        others: Vec<Error>,
+    },
+
+    #[error("`{cmd}` failed{}! Review the output & try again", exited(code))]
+    Git {
+        cmd: String,
+        /// `None` if it was killed by a signal.
+        code: Option<i32>,
+        stderr: String,
+    },
+
+    /// A pattern of `ignore` in `nedots.yml` isn't a glob.
+    #[error("`{pattern}` in `ignore` isn't a valid glob ({err})")]
+    Ignore {
+        pattern: String,
+        err: glob::PatternError,
+    },
+
+    /// `config check` found problems, each kept as the report's `data`.
+    #[error("{msg}")]
+    Invalid {
+        msg: String,
+        report: crate::models::report::Report,
+    },
+
+    #[error("Include cycle: {0}")]
+    IncludeCycle(String),
+
+    /// A command that failed part way, & what it did up to then.
+    #[error("{msg}")]
+    Incomplete {
+        msg: String,
+        report: crate::models::report::Report,
+    },
 
     #[error(transparent)]
-    IoError(#[from] std::io::Error),
+    Io(#[from] std::io::Error),
 
     #[error("Failed to make dir @ `{path}` ({err})")]
     MakeDir { path: String, err: std::io::Error },
@@ -12,12 +78,167 @@ pub enum Error {
     #[error("No metadata ({0})")]
     Metadata(String),
 
-    #[error("No modified time ({0})")]
-    ModifiedTime(String),
+    #[error("`{0}` not found")]
+    NotFound(String),
+
+    /// Anything without a variant of its own.
+    #[error(transparent)]
+    Other(anyhow::Error),
 
-    #[error("Failed to remove dir @ `{path}` ({err})")]
-    RemoveDir { path: String, err: std::io::Error },
+    #[error("Permission denied @ `{}` ({err})", path.display())]
+    PermissionDenied { path: PathBuf, err: std::io::Error },
+
+    #[error("Failed to parse `{}` ({err})", path.display())]
+    Parse {
+        path: PathBuf,
+        err: crate::models::diagnostic::Diagnostic,
+    },
 
     #[error("Failed to resolve `{path}` ({err})")]
     ResolvePath { path: String, err: std::io::Error },
+
+    #[error("`${0}` is undefined")]
+    UndefinedVar(String),
+
+    #[error("`{0}` is unreachable")]
+    Unreachable(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Exit codes of `nedots`, so that scripts can tell failures apart. Usage
+/// errors exit with 2, as `clap` decides.
+pub mod exit {
+    /// Anything not listed below.
+    pub const FAILURE: u8 = 1;
+    /// Some things failed, but the rest were done, see `Error::Incomplete`.
+    pub const INCOMPLETE: u8 = 3;
+    /// `nedots.yml` couldn't be parsed, or is invalid.
+    pub const CONFIG: u8 = 4;
+    /// A path doesn't exist or doesn't resolve, or a key matched nothing.
+    pub const NOT_FOUND: u8 = 5;
+    pub const PERMISSION_DENIED: u8 = 6;
+    /// Copying, or some other file system operation, failed.
+    pub const IO: u8 = 7;
+    pub const GIT: u8 = 8;
+    /// A program other than `git`, e.g. a package manager, failed.
+    pub const COMMAND: u8 = 9;
+    /// A remote, & its mirrors, couldn't be reached.
+    pub const UNREACHABLE: u8 = 10;
+    /// As though interrupted by Ctrl+C.
+    pub const ABORTED: u8 = 130;
+}
+
+impl Error {
+    /// Which of `exit` the process should exit with.
+    pub fn exit_code(&self) -> u8 {
+        if let Some(err) = self.io_error() {
+            if let Some(code) = io_exit_code(err) {
+                return code;
+            }
+        }
+
+        match self {
+            Error::Aborted(_) => exit::ABORTED,
+            Error::Ambiguous { .. } | Error::NotFound(_) | Error::ResolvePath { .. } => {
+                exit::NOT_FOUND
+            }
+            Error::Command { .. } => exit::COMMAND,
+            Error::Config { .. }
+            | Error::Ignore { .. }
+            | Error::IncludeCycle(_)
+            | Error::Invalid { .. }
+            | Error::Parse { .. }
+            | Error::UndefinedVar(_) => exit::CONFIG,
+            Error::CopyMany { first, .. } => first.exit_code(),
+            Error::Copy { .. } | Error::Io(_) | Error::MakeDir { .. } | Error::Metadata(_) => {
+                exit::IO
+            }
+            Error::Git { .. } => exit::GIT,
+            Error::Incomplete { .. } => exit::INCOMPLETE,
+            Error::Other(err) => exit_code(err),
+            Error::PermissionDenied { .. } => exit::PERMISSION_DENIED,
+            Error::Unreachable(_) => exit::UNREACHABLE,
+        }
+    }
+
+    fn io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Error::Copy { err, .. } | Error::Io(err) | Error::MakeDir { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Which of `exit` the process should exit with, given the first `Error`, or
+/// `std::io::Error`, that caused `err`.
+///
+/// * `err`: &anyhow::Error
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<Error>() {
+            return err.exit_code();
+        }
+
+        if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            return io_exit_code(err).unwrap_or(exit::IO);
+        }
+    }
+
+    exit::FAILURE
+}
+
+/// * `err`: &std::io::Error
+fn io_exit_code(err: &std::io::Error) -> Option<u8> {
+    match err.kind() {
+        std::io::ErrorKind::PermissionDenied => Some(exit::PERMISSION_DENIED),
+        std::io::ErrorKind::NotFound => Some(exit::NOT_FOUND),
+        _ => None,
+    }
+}
+
+/// E.g. ` with exit code 128`, or nothing if `code` is unknown.
+///
+/// * `code`: &Option<i32>
+fn exited(code: &Option<i32>) -> String {
+    code.map(|code| format!(" with exit code {}", code))
+        .unwrap_or_default()
+}
+
+impl From<anyhow::Error> for Error {
+    /// Recover `Error` from `err` if that's what it is, otherwise it's `Other`.
+    ///
+    /// * `err`: anyhow::Error
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast::<Error>().unwrap_or_else(Error::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exit, exit_code, Error};
+    use anyhow::Context;
+
+    #[test]
+    fn exit_codes_follow_the_cause() {
+        let git = Error::Git {
+            cmd: "git pull".to_string(),
+            code: Some(128),
+            stderr: String::new(),
+        };
+        assert_eq!(
+            git.to_string(),
+            "`git pull` failed with exit code 128! Review the output & try again"
+        );
+
+        let err = anyhow::Error::from(git).context("Failed to sync");
+        assert_eq!(exit_code(&err), exit::GIT);
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let err = anyhow::Error::from(Error::Io(denied));
+        assert_eq!(exit_code(&err), exit::PERMISSION_DENIED);
+
+        let err: anyhow::Result<()> = Err(anyhow::anyhow!("?")).context("Oops");
+        assert_eq!(exit_code(&err.unwrap_err()), exit::FAILURE);
+    }
 }
@@ -1,14 +1,39 @@
+//! Manage configuration files/(ne)dots, from the `nedots` CLI or as a library.
+//!
+//! `Nedots` gathers, installs, backs up & restores the `sources` of a
+//! `Config`, returning a `Report` of what was done, or an `Error`. The CLI is
+//! built on the same.
+
+pub(crate) mod cli;
 pub(crate) mod cmd;
-pub(crate) mod errors;
+pub mod errors;
 pub(crate) mod models;
+mod nedots;
 pub(crate) mod ops;
 pub(crate) mod utils;
 
-pub use cmd::nedots::RootCmd;
+pub(crate) use cmd::nedots::RootCmd;
+pub use errors::Error;
+pub use models::{
+    config::Config,
+    diagnostic::Diagnostic,
+    git_repo::{CloneFilter, GitRepo, RepoStatus},
+    hooks::Hooks,
+    packages::Manager,
+    report::{Action, Report},
+    status::{GitRepoStatus, SourceState, SourceStatus, Status},
+};
+pub use nedots::Nedots;
+
+/// Run the `nedots` CLI, as the binary does. Not part of the library's API.
+#[doc(hidden)]
+pub fn cli_main() -> std::process::ExitCode {
+    cli::main()
+}
 
 /// Implementors will take steps to `Initialize` before runtime. They return `T`
 /// and `V` is passed to `init` and is required for valid `Initialization`.
-pub trait Initialize<T, V> {
+pub(crate) trait Initialize<T, V> {
     /// Use args in order to initialize and return `T`.
     fn init(&self, args: &V) -> anyhow::Result<T>;
 }
@@ -16,29 +41,28 @@ pub trait Initialize<T, V> {
 /// Implementors will `Run`. Shares similarities with `Execute`, but differs
 /// because `exec` is typically called to `run` implementor so that the logical
 /// scope of `Run` is constrained to its bare necessities.
-pub trait Run {
-    fn run(&self) -> anyhow::Result<()>;
+pub(crate) trait Run {
+    fn run(&self) -> anyhow::Result<Report>;
 }
 
-pub trait RunWith<T> {
-    fn run_with(&self, with: &T) -> anyhow::Result<()>;
+/// Same as `Run`, with `T`. Returns a `Report` of what was done, printed with
+/// `--output json`.
+pub(crate) trait RunWith<T> {
+    fn run_with(&self, with: &T) -> anyhow::Result<Report>;
 }
 
-pub trait Execute: clap::Args + Run {
-    fn exec(&self) -> anyhow::Result<()> {
+pub(crate) trait Execute: clap::Args + Run {
+    fn exec(&self) -> anyhow::Result<Report> {
         self.run()
     }
 }
 
 /// Implementors will 'Execute' - they will run some code with the intent of
 /// notifying the user during or after runtime.
-pub trait ExecuteWith<T, V>: clap::Args + RunWith<V> {
+pub(crate) trait ExecuteWith<T, V>: clap::Args + RunWith<V> {
     /// Execute with `T`, so `T` is passed to `exec` and is required for valid
     /// runtime.
     ///
     /// * `with`: `&T`, some data required for execution.
-    fn exec_with(&self, with: &T) -> anyhow::Result<()>;
+    fn exec_with(&self, with: &T) -> anyhow::Result<Report>;
 }
-
-/// Marker trait.
-pub trait BasicCommand {}
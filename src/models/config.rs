@@ -1,63 +1,401 @@
-use super::git_repo::GitRepo;
-use crate::utils::paths::ResolvePath;
-use anyhow::Context;
+use super::{
+    diagnostic::Diagnostic,
+    git_repo::GitRepo,
+    hooks::{self, Hooks},
+    layer::{self, Layer, Origins},
+    packages::Manager,
+};
+use crate::{
+    errors::Error,
+    utils::paths::{self, ResolvePath},
+};
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
+    io::ErrorKind,
     path::{Path, PathBuf},
 };
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Config {
+    /// Path of the file this `Config` was read from.
     #[serde(skip, default)]
+    pub file: PathBuf,
+
+    /// Where the dots repository is cloned, relative to $HOME unless absolute.
+    /// Defaults to `$XDG_DATA_HOME/nedots`.
+    #[serde(default, skip_serializing_if = "is_empty")]
     pub root: PathBuf,
 
-    #[serde(skip, default)]
+    /// Where copies of `sources` are kept, relative to `root` unless absolute.
+    /// Defaults to `dots`.
+    #[serde(default, skip_serializing_if = "is_empty")]
     pub dots_dir: PathBuf,
 
-    #[serde(skip, default)]
+    /// Where backups are kept, relative to `root` unless absolute. Defaults to
+    /// `backups`.
+    #[serde(default, skip_serializing_if = "is_empty")]
     pub backup_dir: PathBuf,
 
+    /// Other files to merge into this one, relative to it. Files, globs, &
+    /// directories of `.yml` files, e.g. `conf.d`, may be included.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Remote git repository, `ssh` (`git@`) should be preferred over `https://`.
+    /// Required, but may be set by an included file. May contain `$VAR`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub remote: String,
+
+    /// Additional remotes, e.g. a backup forge or a bare repository on a NAS.
+    /// `sync` pushes to all of them, and pulls from them if `remote` is
+    /// unreachable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    /// Files & directories to manage, relative to $HOME or absolute. May
+    /// begin with `~`, & contain `$VAR` or `${VAR}`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub sources: Vec<PathBuf>,
+    /// Hooks of individual `sources`, keyed by the source as it's listed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub source_hooks: BTreeMap<PathBuf, Hooks>,
+    /// Globs of files & directories within `sources` that aren't gathered,
+    /// matched against their name or path relative to the source, e.g.
+    /// `*.swp` or `cache/**`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Git repositories to clone, rather than copy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub git_repos: Vec<GitRepo>,
+
+    /// Register `git_repos` as submodules of the root repository, so that the
+    /// exact commit of each is pinned.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub submodules: bool,
+
+    /// Packages to install, keyed by package manager, see `nedots packages`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub packages: BTreeMap<Manager, Vec<String>>,
+
+    /// Commands run once before & after `install`, `gather`, `sync` &
+    /// `backup`.
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
 }
 
 pub const DEFAULT_DOTS_DIR: &str = "dots";
 pub const DEFAULT_BACKUP_DIR: &str = "backups";
+/// Under `dots_dir`, sources relative to $HOME are kept in `HOME`, & anything
+/// else in `ROOT`, so that `dots` doesn't depend on the user or their $HOME.
+pub const HOME_PREFIX: &str = "HOME";
+pub const ROOT_PREFIX: &str = "ROOT";
+/// Config committed to the root repository, used when the user has none of
+/// their own. May be TOML or JSON too.
+pub const REPO_CONFIG_FILE: &str = "nedots.yml";
+/// Host profiles in the root repository, e.g. `hosts/laptop.yml`, layered on
+/// top of its `nedots.yml` by `bootstrap`.
+pub const HOSTS_DIR: &str = "hosts";
+/// Written next to `nedots.yml` by `init`, see `schema`.
+pub const SCHEMA_FILE: &str = "nedots.schema.json";
 
 pub fn read(path: &Path) -> anyhow::Result<Config> {
+    Ok(read_with_origins(path)?.0)
+}
+
+/// Read `path`, merged with every file it includes, see `layer`. Also returns
+/// which file each part of `Config` came from.
+///
+/// * `path`: &Path
+pub fn read_with_origins(path: &Path) -> anyhow::Result<(Config, Origins)> {
     let path = path.resolve_path()?;
+    let layers = layer::load(&path)?;
 
-    log::trace!("Reading `{}`...", path.display());
-    let raw = std::fs::read_to_string(&path)
-        .with_context(|| format!("Failed to read `{}`", path.display()))?;
+    for layer in &layers {
+        for key in &layer.unknown {
+            log::warn!(
+                "⚠️ Ignoring unknown key `{}` in `{}`",
+                key,
+                layer.path.display()
+            );
+        }
+    }
+
+    log::trace!("Merging {} file(s)...", layers.len());
+    let (mut config, origins) = layer::merge(&layers);
+    config.expand_remotes()?;
+    if config.remote.is_empty() {
+        return Err(Error::Config {
+            path: path.to_path_buf(),
+            msg: "`remote` isn't set in it, or any file it includes".to_string(),
+        }
+        .into());
+    }
 
-    log::trace!("Deserializing...");
-    let config: Config = serde_yaml::from_str(&raw)
-        .with_context(|| format!("Failed to deserialize `{:#?}`", &raw))?;
+    Ok((config, origins))
+}
+
+/// Check `path`, & every file it includes, for anything that would stop
+/// `nedots` from doing what the user expects: syntax errors, unknown keys,
+/// `sources` & `git_repos` that don't exist, are listed twice, or overlap one
+/// another. Each problem is returned with the file it was found in.
+///
+/// * `path`: &Path
+pub fn check(path: &Path) -> anyhow::Result<Vec<(PathBuf, Diagnostic)>> {
+    match layer::load(path) {
+        Ok(layers) => Ok(check_layers(&layers)),
+        Err(err) => match err.downcast::<Error>()? {
+            Error::Parse { path, err } => Ok(vec![(path, err)]),
+            err => Err(err.into()),
+        },
+    }
+}
+
+/// See `check`.
+///
+/// * `layers`: &[Layer], as loaded.
+fn check_layers(layers: &[Layer]) -> Vec<(PathBuf, Diagnostic)> {
+    let mut diagnostics = Vec::new();
+
+    // Problems within each file.
+    for layer in layers {
+        let mut push = |message: String, location| {
+            diagnostics.push((layer.path.clone(), Diagnostic::new(message).at(location)));
+        };
+
+        for key in &layer.unknown {
+            let path: Vec<&str> = key.split('.').collect();
+            push(
+                format!("Unknown key `{}`", key),
+                layer.format.locate(&layer.raw, &path),
+            );
+        }
+
+        let config = &layer.config;
+        for (i, source) in config.sources.iter().enumerate() {
+            if config.sources[..i].contains(source) {
+                let index = i.to_string();
+                push(
+                    format!("`{}` is listed more than once", source.display()),
+                    layer.format.locate(&layer.raw, &["sources", &index]),
+                );
+            }
+        }
+
+        for (i, repo) in config.git_repos.iter().enumerate() {
+            if config.git_repos[..i].iter().any(|r| r.path == repo.path) {
+                let index = i.to_string();
+                push(
+                    format!("`{}` is listed more than once", repo.path.display()),
+                    layer
+                        .format
+                        .locate(&layer.raw, &["git_repos", &index, "path"]),
+                );
+            }
+        }
+    }
+
+    // Problems once every file is merged, reported where each entry is from.
+    let (config, origins) = layer::merge(layers);
+    let locate = |origin: &Path, key: &str, position: &dyn Fn(&Config) -> Option<usize>| {
+        let layer = layers.iter().find(|l| l.path == origin)?;
+        let index = position(&layer.config)?.to_string();
+        match key {
+            "git_repos" => layer.format.locate(&layer.raw, &[key, &index, "path"]),
+            _ => layer.format.locate(&layer.raw, &[key, &index]),
+        }
+    };
+
+    if config.remote.is_empty() {
+        let path = layers.last().map(|l| l.path.clone()).unwrap_or_default();
+        diagnostics.push((path, Diagnostic::new("`remote` isn't set")));
+    }
+
+    let sources: Vec<Result<PathBuf, String>> =
+        config.sources.iter().map(|s| resolve_to_check(s)).collect();
+    for (i, path) in sources.iter().enumerate() {
+        let source = &config.sources[i];
+        let origin = &origins.sources[i];
+        let location = locate(origin, "sources", &|c| {
+            c.sources.iter().position(|s| s == source)
+        });
+
+        let mut push = |message: String| {
+            diagnostics.push((origin.clone(), Diagnostic::new(message).at(location)));
+        };
+
+        let path = match path {
+            Ok(path) => path,
+            Err(message) => {
+                push(message.clone());
+                continue;
+            }
+        };
+
+        if let Some(parent) = sources
+            .iter()
+            .flatten()
+            .find(|s| path != *s && path.starts_with(s))
+        {
+            push(format!(
+                "`{}` is inside `{}`, also a source",
+                source.display(),
+                parent.display()
+            ));
+        }
+    }
+
+    for (i, repo) in config.git_repos.iter().enumerate() {
+        let origin = &origins.git_repos[i];
+        let location = locate(origin, "git_repos", &|c| {
+            c.git_repos.iter().position(|r| r.path == repo.path)
+        });
+
+        let mut push = |message: String| {
+            diagnostics.push((origin.clone(), Diagnostic::new(message).at(location)));
+        };
+
+        let path = match resolve_to_check(&repo.path) {
+            Ok(path) => path,
+            Err(message) => {
+                push(message);
+                continue;
+            }
+        };
+
+        if let Some(source) = sources
+            .iter()
+            .flatten()
+            .find(|s| path.starts_with(s) || s.starts_with(&path))
+        {
+            push(format!(
+                "`{}` overlaps `{}` in `sources`",
+                repo.path.display(),
+                source.display()
+            ));
+        }
+    }
+
+    for layer in layers {
+        for source in layer.config.source_hooks.keys() {
+            if !config.sources.contains(source) {
+                let key = source.to_string_lossy();
+                let location = layer.format.locate(&layer.raw, &["source_hooks", &key]);
+                let message = format!("`{}` has hooks, but isn't in `sources`", key);
+                diagnostics.push((layer.path.clone(), Diagnostic::new(message).at(location)));
+            }
+        }
+
+        for pattern in &layer.config.ignore {
+            if let Err(err) = glob::Pattern::new(pattern) {
+                let message = format!("`{}` in `ignore` isn't a valid glob, {}", pattern, err);
+                diagnostics.push((layer.path.clone(), Diagnostic::new(message)));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Resolve `path` the same way `resolve_sources` does at runtime, or say why
+/// it doesn't.
+///
+/// * `path`: &Path
+fn resolve_to_check(path: &Path) -> Result<PathBuf, String> {
+    path.resolve_path()
+        .map_err(|err| match err.downcast_ref::<Error>() {
+            Some(Error::ResolvePath { err, .. }) if err.kind() == ErrorKind::NotFound => {
+                format!("`{}` doesn't exist", path.display())
+            }
+            _ => format!("`{}` can't be resolved ({})", path.display(), err),
+        })
+}
+
+fn is_empty(path: &Path) -> bool {
+    path.as_os_str().is_empty()
+}
+
+/// Where the dots repository is cloned, unless configured otherwise.
+pub fn default_root() -> PathBuf {
+    directories::BaseDirs::new()
+        .expect("No BaseDirs")
+        .data_local_dir()
+        .join("nedots")
+}
 
-    Ok(config)
+/// JSON Schema of `nedots.yml`, for editors to autocomplete & validate with.
+pub fn schema() -> anyhow::Result<String> {
+    let schema = schemars::schema_for!(Config);
+    Ok(serde_json::to_string_pretty(&schema)?)
 }
 
 pub fn get_sample() -> Config {
     Config {
+        file: PathBuf::default(),
         root: PathBuf::default(),
         dots_dir: PathBuf::default(),
         backup_dir: PathBuf::default(),
+        include: Vec::new(),
         remote: "git@git.sr.ht:~nedia/nedots".to_string(),
+        mirrors: Vec::new(),
         sources: vec![".config/nedots".into()],
+        source_hooks: BTreeMap::new(),
+        ignore: Vec::new(),
         git_repos: vec![GitRepo {
             remote: "git@git.sr.ht:~nedia/config.nvim".to_string(),
             path: ".config/nvim".into(),
+            ..Default::default()
         }],
+        submodules: false,
+        packages: BTreeMap::new(),
+        hooks: Hooks::default(),
     }
 }
 
 impl Config {
-    pub fn resolve_paths(mut self) -> Config {
+    /// Resolve core directories, `sources` & `git_repos`.
+    ///
+    /// * `lenient`: bool, see `resolve_sources`.
+    pub fn resolve_paths(mut self, lenient: bool) -> anyhow::Result<Config> {
         self = self.resolve_dirs();
-        self = self.resolve_sources();
-        self
+        self.resolve_sources(lenient)
+    }
+
+    /// Decide where `root`, `dots_dir` & `backup_dir` are. Each argument, if
+    /// given, takes precedence over `nedots.yml`, otherwise defaults apply, see
+    /// `default_root`, `DEFAULT_DOTS_DIR` & `DEFAULT_BACKUP_DIR`.
+    ///
+    /// * `root`: Option<&Path>
+    /// * `dots_dir`: Option<&Path>
+    /// * `backup_dir`: Option<&Path>
+    pub fn set_dirs(
+        &mut self,
+        root: Option<&Path>,
+        dots_dir: Option<&Path>,
+        backup_dir: Option<&Path>,
+    ) -> anyhow::Result<()> {
+        fn pick(arg: Option<&Path>, configured: &Path) -> Option<PathBuf> {
+            match arg {
+                Some(arg) => Some(arg.to_path_buf()),
+                None if !is_empty(configured) => Some(configured.to_path_buf()),
+                None => None,
+            }
+        }
+
+        self.root = match pick(root, &self.root) {
+            Some(root) => paths::expand_path(&root)?,
+            None => default_root(),
+        };
+
+        let dots_dir = pick(dots_dir, &self.dots_dir).unwrap_or(DEFAULT_DOTS_DIR.into());
+        self.dots_dir = self.root.join(paths::expand(&dots_dir.to_string_lossy())?);
+
+        let backup_dir = pick(backup_dir, &self.backup_dir).unwrap_or(DEFAULT_BACKUP_DIR.into());
+        self.backup_dir = self
+            .root
+            .join(paths::expand(&backup_dir.to_string_lossy())?);
+
+        Ok(())
     }
 
     pub fn resolve_dirs(mut self) -> Config {
@@ -70,13 +408,11 @@ impl Config {
             Err(err) => log_error(err),
         }
 
-        self.dots_dir = self.root.join(DEFAULT_DOTS_DIR);
         match self.dots_dir.resolve_path() {
             Ok(path) => self.dots_dir = path,
             Err(err) => log_error(err),
         }
 
-        self.backup_dir = self.root.join(DEFAULT_BACKUP_DIR);
         match self.backup_dir.resolve_path() {
             Ok(path) => self.backup_dir = path,
             Err(err) => log_error(err),
@@ -85,72 +421,265 @@ impl Config {
         self
     }
 
-    pub fn resolve_sources(mut self) -> Config {
-        self.sources = self
-            .sources
-            .into_iter()
-            .map(|s| match s.resolve_path() {
-                Ok(path) => path,
-                Err(err) => {
-                    log::error!("❌ {}", err);
-                    Path::new("").to_path_buf()
-                }
-            })
-            .filter(|s| s.ne(Path::new("")))
-            .collect();
-
-        self.git_repos = self
-            .git_repos
-            .into_iter()
-            .map(|mut gr| match gr.path.resolve_path() {
+    /// Check that `sources` resolve, & resolve `git_repos`. `sources` are
+    /// left as they're written, see `dots_path`. Any that don't resolve are an
+    /// error, unless `lenient`, in which case they're skipped with a warning.
+    ///
+    /// * `lenient`: bool
+    pub fn resolve_sources(mut self, lenient: bool) -> anyhow::Result<Config> {
+        let mut unresolved: Vec<anyhow::Error> = Vec::new();
+
+        let mut sources = Vec::new();
+        for source in self.sources {
+            match source.resolve_path() {
+                Ok(_) => sources.push(source),
+                Err(err) => unresolved.push(err),
+            }
+        }
+        self.sources = sources;
+
+        let mut git_repos = Vec::new();
+        for mut repo in self.git_repos {
+            match repo.path.resolve_path() {
                 Ok(path) => {
-                    gr.path = path;
-                    gr
-                }
-                Err(err) => {
-                    log::error!("❌ {}", err);
-                    gr.path = Path::new("").to_path_buf();
-                    gr
+                    repo.path = path;
+                    git_repos.push(repo);
                 }
-            })
-            .filter(|gr| gr.path.ne(Path::new("")))
-            .collect();
+                Err(err) => unresolved.push(err),
+            }
+        }
+        self.git_repos = git_repos;
 
-        self
+        if unresolved.is_empty() {
+            return Ok(self);
+        }
+
+        for err in &unresolved {
+            match lenient {
+                true => log::warn!("⚠️ Skipping, {}", err),
+                false => log::error!("❌ {}", err),
+            }
+        }
+
+        if !lenient {
+            return Err(Error::Config {
+                path: self.file,
+                msg: format!(
+                    "{} of `sources` & `git_repos` failed to resolve, see `nedots config check` \
+                    or pass `--lenient` to skip them",
+                    unresolved.len()
+                ),
+            }
+            .into());
+        }
+
+        Ok(self)
     }
 
-    pub fn get_sources_as_hashmap(&self) -> HashMap<&str, PathBuf> {
-        let mut all_parts: Vec<&str> = Vec::new();
-        for pb in &self.sources {
-            let _: Vec<&str> = pb
-                .to_str()
-                .unwrap_or("")
-                .split('/')
-                .map(|part| {
-                    all_parts.push(part);
-                    part
-                })
-                .collect();
-        }
-
-        all_parts.sort();
-        all_parts.dedup_by(|a, b| a.eq(&b));
-
-        let mut hash_map: HashMap<&str, PathBuf> = HashMap::new();
-        for part in &all_parts {
-            if !part.is_empty() {
-                hash_map.insert(
-                    part,
-                    self.sources
-                        .iter()
-                        .find(|s| s.to_str().unwrap_or("").contains(part))
-                        .unwrap()
-                        .to_path_buf(),
-                );
+    /// Expand variables in `remote`, `mirrors` & the remote of each of
+    /// `git_repos`.
+    pub fn expand_remotes(&mut self) -> anyhow::Result<()> {
+        self.remote = paths::expand(&self.remote)?;
+        for mirror in &mut self.mirrors {
+            *mirror = paths::expand(mirror)?;
+        }
+
+        for repo in &mut self.git_repos {
+            repo.remote = paths::expand(&repo.remote)?;
+            for mirror in &mut repo.mirrors {
+                *mirror = paths::expand(mirror)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Where the copy of `source` is kept in `dots_dir`. A source that begins
+    /// with a variable is kept under the variable's name, e.g.
+    /// `$XDG_CONFIG_HOME/nvim` @ `dots/XDG_CONFIG_HOME/nvim`, so that it
+    /// installs correctly on machines where the variable differs. Anything
+    /// else is kept according to its local path, see `dots_layout`.
+    ///
+    /// * `source`: &Path, as it's written in `sources`.
+    pub fn dots_path(&self, source: &Path) -> anyhow::Result<PathBuf> {
+        self.dots_path_with(source, paths::env_var)
+    }
+
+    /// See `dots_path`, with variables looked up in `env`.
+    ///
+    /// * `source`: &Path
+    /// * `env`: impl Fn(&str) -> Option<String>
+    fn dots_path_with(
+        &self,
+        source: &Path,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> anyhow::Result<PathBuf> {
+        let raw = source.to_string_lossy();
+        if let Some((var, rest)) = paths::split_var(&raw) {
+            if var != "HOME" {
+                // Undefined variables are an error, even if we don't need them.
+                paths::expand_with(&raw, &env)?;
+                let rest = paths::expand_with(rest.trim_start_matches('/'), &env)?;
+                return Ok(self.dots_dir.join(var).join(rest));
             }
         }
 
-        log::debug!("Keyed sources `{:#?}`", &hash_map);
-        hash_map
+        let path = PathBuf::from(paths::expand_with(&raw, &env)?).prepend_home();
+        Ok(self.dots_layout(&path))
+    }
+
+    /// Where local `path` is kept in `dots_dir`, e.g. `$HOME/.bashrc` @
+    /// `dots/HOME/.bashrc`, & `/etc/hostname` @ `dots/ROOT/etc/hostname`.
+    ///
+    /// * `path`: &Path, absolute.
+    pub fn dots_layout(&self, path: &Path) -> PathBuf {
+        let relative = path.strip_home();
+        match relative.is_absolute() {
+            true => crate::utils::join_paths(&self.dots_dir.join(ROOT_PREFIX), &relative),
+            false => self.dots_dir.join(HOME_PREFIX).join(relative),
+        }
+    }
+
+    /// Environment variables of every hook, see `hooks`.
+    pub fn hook_env(&self) -> hooks::Env {
+        vec![
+            ("NEDOTS_ROOT", self.root.clone().into()),
+            ("NEDOTS_DOTS_DIR", self.dots_dir.clone().into()),
+        ]
+    }
+
+    /// Hooks of `source`, as it's listed in `sources`.
+    ///
+    /// * `source`: &Path
+    pub fn hooks_of(&self, source: &Path) -> Hooks {
+        self.source_hooks.get(source).cloned().unwrap_or_default()
+    }
+
+    /// The root repository, `remote` & `mirrors` @ `root`.
+    pub fn root_repo(&self) -> GitRepo {
+        GitRepo {
+            mirrors: self.mirrors.clone(),
+            ..GitRepo::new(&self.remote, &self.root)
+        }
+    }
+
+    /// Path of the submodule representing `repo`, relative to `root`. Mirrors
+    /// the layout of `sources` in `dots_dir`.
+    ///
+    /// * `repo`: &GitRepo, `path` is expected to be absolute.
+    pub fn submodule_path(&self, repo: &GitRepo) -> PathBuf {
+        let path = self.dots_layout(&repo.path);
+        match path.strip_prefix(&self.root) {
+            Ok(path) => path.to_path_buf(),
+            Err(_) => path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_layers, schema, Config, Layer};
+    use std::path::Path;
+
+    fn check(raw: &str) -> Vec<String> {
+        let layer = Layer::parse(Path::new("nedots.yml"), raw.to_string()).unwrap();
+        check_layers(&[layer])
+            .iter()
+            .map(|(_, diagnostic)| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let err =
+            Layer::parse(Path::new("nedots.yml"), "remote: x\nsources: {}\n".into()).unwrap_err();
+        assert!(err.to_string().contains("(2:10: sources: invalid type"));
+    }
+
+    #[test]
+    fn check_finds_unknown_duplicate_and_overlapping_entries() {
+        let raw = "remote: x
+sorces: []
+sources:
+  - /etc
+  - /etc/hostname
+  - /etc
+git_repos:
+  - remote: y
+    path: /etc/hostname
+    dpeth: 1
+";
+        assert_eq!(
+            check(raw),
+            [
+                "2:1: Unknown key `sorces`",
+                "10:5: Unknown key `git_repos.0.dpeth`",
+                "6:5: `/etc` is listed more than once",
+                "5:5: `/etc/hostname` is inside `/etc`, also a source",
+                "9:5: `/etc/hostname` overlaps `/etc` in `sources`",
+            ]
+        );
+
+        let raw = "remote: x\nsources:\n  - /nedots/missing\n";
+        assert_eq!(check(raw), ["3:5: `/nedots/missing` doesn't exist"]);
+    }
+
+    #[test]
+    fn schema_omits_runtime_fields() {
+        let schema: serde_json::Value = serde_json::from_str(&schema().unwrap()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+
+        assert!(properties.contains_key("git_repos"));
+        assert!(!properties.contains_key("file"));
+        assert_eq!(schema["additionalProperties"], false);
+    }
+
+    #[test]
+    fn dots_path_is_stable_across_machines() {
+        let config = Config {
+            dots_dir: "/dots".into(),
+            ..Default::default()
+        };
+
+        let env = |var: &str| (var == "NEDOTS_TEST_DIR").then(|| "/somewhere/else".to_string());
+        let dots_path = |source: &str| config.dots_path_with(Path::new(source), env);
+        assert_eq!(
+            dots_path("$NEDOTS_TEST_DIR/nvim").unwrap(),
+            Path::new("/dots/NEDOTS_TEST_DIR/nvim")
+        );
+        assert_eq!(
+            dots_path("${XDG_CONFIG_HOME}/sway").unwrap(),
+            Path::new("/dots/XDG_CONFIG_HOME/sway")
+        );
+        assert_eq!(
+            dots_path("/etc/hostname").unwrap(),
+            Path::new("/dots/ROOT/etc/hostname")
+        );
+        assert_eq!(
+            dots_path("~/.bashrc").unwrap(),
+            Path::new("/dots/HOME/.bashrc")
+        );
+        assert!(dots_path("$NEDOTS_UNDEFINED/x").is_err());
+    }
+
+    #[test]
+    fn dirs_from_args_override_config() {
+        let mut config = Config {
+            root: "/from/config".into(),
+            backup_dir: "/mnt/backups".into(),
+            ..Default::default()
+        };
+
+        config.set_dirs(None, None, None).unwrap();
+        assert_eq!(config.root, Path::new("/from/config"));
+        assert_eq!(config.dots_dir, Path::new("/from/config/dots"));
+        assert_eq!(config.backup_dir, Path::new("/mnt/backups"));
+
+        let root = Path::new("/from/args");
+        config
+            .set_dirs(Some(root), Some(Path::new("d")), None)
+            .unwrap();
+        assert_eq!(config.root, root);
+        assert_eq!(config.dots_dir, Path::new("/from/args/d"));
     }
 }
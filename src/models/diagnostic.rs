@@ -0,0 +1,48 @@
+//! Problems found in `nedots.yml`, or its TOML & JSON equivalents, & where
+//! they were found.
+
+/// A problem found @ `line` & `column` of `nedots.yml`, both start at 1.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Diagnostic {
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    /// Set the location of this `Diagnostic`, if it's known.
+    ///
+    /// * `location`: Option<(usize, usize)>, line & column.
+    pub fn at(mut self, location: Option<(usize, usize)>) -> Self {
+        if let Some((line, column)) = location {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+}
+
+impl From<serde_yaml::Error> for Diagnostic {
+    fn from(err: serde_yaml::Error) -> Self {
+        let location = err.location().map(|l| (l.line(), l.column()));
+
+        Diagnostic::new(super::format::strip_location(err.to_string(), location)).at(location)
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}: {}", line, column, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
@@ -0,0 +1,445 @@
+//! Config files may be YAML, TOML or JSON, determined by their extension.
+
+use super::diagnostic::Diagnostic;
+use crate::utils::{json, yaml};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    #[default]
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Yaml, Format::Toml, Format::Json];
+
+    /// Format of `path`, by extension. Anything unrecognised is assumed to be
+    /// YAML.
+    ///
+    /// * `path`: &Path
+    pub fn from_path(path: &Path) -> Format {
+        let ext = path.extension().unwrap_or_default().to_string_lossy();
+        Format::ALL
+            .into_iter()
+            .find(|format| format.extensions().contains(&ext.as_ref()))
+            .unwrap_or_default()
+    }
+
+    /// `path` if it exists, otherwise the same file with any recognised
+    /// extension that does, e.g. `nedots.toml` given `nedots.yml`.
+    ///
+    /// * `path`: &Path
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        if path.exists() {
+            return Some(path.to_path_buf());
+        }
+
+        Format::ALL
+            .iter()
+            .flat_map(|format| format.extensions())
+            .map(|ext| path.with_extension(ext))
+            .find(|path| path.exists())
+    }
+
+    /// Recognised extensions, the first is preferred.
+    pub fn extensions(&self) -> &[&'static str] {
+        match self {
+            Format::Yaml => &["yml", "yaml"],
+            Format::Toml => &["toml"],
+            Format::Json => &["json"],
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        self.extensions()[0]
+    }
+
+    /// A comment that points editors at `schema`, if the format has comments.
+    ///
+    /// * `schema`: &str, path of schema, relative to the config file.
+    pub fn schema_modeline(&self, schema: &str) -> Option<String> {
+        match self {
+            Format::Yaml => Some(format!("# yaml-language-server: $schema={}\n", schema)),
+            Format::Toml => Some(format!("#:schema {}\n", schema)),
+            Format::Json => None,
+        }
+    }
+
+    pub fn serialize<T: serde::Serialize>(&self, value: &T) -> anyhow::Result<String> {
+        Ok(match self {
+            Format::Yaml => serde_yaml::to_string(value)?,
+            Format::Toml => toml::to_string(value)?,
+            Format::Json => serde_json::to_string_pretty(value)? + "\n",
+        })
+    }
+
+    /// Deserialize `raw`, calling `unknown` with the path of each unknown
+    /// key, e.g. `git_repos.0.remtoe`, rather than failing on them.
+    ///
+    /// * `raw`: &str
+    /// * `unknown`: impl FnMut(String)
+    pub fn deserialize<T: serde::de::DeserializeOwned>(
+        &self,
+        raw: &str,
+        mut unknown: impl FnMut(String),
+    ) -> Result<T, Diagnostic> {
+        let mut callback = |path: serde_ignored::Path| unknown(path.to_string());
+        match self {
+            Format::Yaml => {
+                let de = serde_yaml::Deserializer::from_str(raw);
+                serde_ignored::deserialize(de, callback).map_err(Diagnostic::from)
+            }
+            Format::Toml => {
+                let de = toml::Deserializer::new(raw);
+                serde_ignored::deserialize(de, &mut callback).map_err(|err| {
+                    let location = err.span().map(|span| line_column(raw, span.start));
+                    Diagnostic::new(err.message().to_string()).at(location)
+                })
+            }
+            Format::Json => {
+                let mut de = serde_json::Deserializer::from_str(raw);
+                serde_ignored::deserialize(&mut de, callback).map_err(|err| {
+                    let location = Some((err.line(), err.column()));
+                    Diagnostic::new(strip_location(err.to_string(), location)).at(location)
+                })
+            }
+        }
+    }
+
+    /// Line & column of the value @ `path` in `raw`, or of its key if the
+    /// last of `path` is one.
+    ///
+    /// * `raw`: &str
+    /// * `path`: &[&str], see `yaml::locate`.
+    pub fn locate(&self, raw: &str, path: &[&str]) -> Option<(usize, usize)> {
+        let offset = match self {
+            Format::Yaml => return yaml::locate(raw, path),
+            Format::Toml => locate_toml(raw, path)?,
+            Format::Json => json::locate(raw, path)?,
+        };
+        Some(line_column(raw, offset))
+    }
+
+    /// Append `item` to top-level list `key`. YAML & TOML are edited in
+    /// place, so that comments survive, JSON, which has none, is rewritten.
+    ///
+    /// * `raw`: &str
+    /// * `key`: &str
+    /// * `item`: &impl serde::Serialize
+    pub fn append_to_list(
+        &self,
+        raw: &str,
+        key: &str,
+        item: &impl serde::Serialize,
+    ) -> anyhow::Result<String> {
+        match self {
+            Format::Yaml => yaml::append_to_list(raw, key, &serde_yaml::to_value(item)?),
+            Format::Toml => append_to_toml_list(raw, key, item),
+            Format::Json => {
+                let item = serde_json::to_value(item)?;
+                self.edit(raw, |map| match map.get_mut(key) {
+                    Some(serde_json::Value::Array(items)) => items.push(item),
+                    _ => {
+                        map.insert(key.to_string(), vec![item].into());
+                    }
+                })
+            }
+        }
+    }
+
+    /// Remove items of top-level list `key` that satisfy `predicate`. Returns
+    /// the edited file & the number of items removed, see `append_to_list`.
+    ///
+    /// * `raw`: &str
+    /// * `key`: &str
+    /// * `predicate`: called with each item of the list.
+    pub fn remove_from_list(
+        &self,
+        raw: &str,
+        key: &str,
+        predicate: impl Fn(&serde_yaml::Value) -> bool,
+    ) -> anyhow::Result<(String, usize)> {
+        if *self == Format::Yaml {
+            return yaml::remove_from_list(raw, key, predicate);
+        }
+
+        let matches = |item: &serde_json::Value| {
+            serde_yaml::to_value(item).is_ok_and(|value| predicate(&value))
+        };
+
+        if *self == Format::Toml {
+            let map: serde_json::Map<String, serde_json::Value> = self
+                .deserialize(raw, |_| ())
+                .map_err(|err| anyhow::anyhow!("{}", err))?;
+            let indices: Vec<usize> = match map.get(key) {
+                Some(serde_json::Value::Array(items)) => {
+                    (0..items.len()).filter(|&i| matches(&items[i])).collect()
+                }
+                _ => Vec::new(),
+            };
+            let raw = remove_from_toml_list(raw, key, &indices)?;
+            return Ok((raw, indices.len()));
+        }
+
+        let mut removed = 0;
+        let raw = self.edit(raw, |map| {
+            if let Some(serde_json::Value::Array(items)) = map.get_mut(key) {
+                let len = items.len();
+                items.retain(|item| !matches(item));
+                removed = len - items.len();
+            }
+        })?;
+
+        Ok((raw, removed))
+    }
+
+    /// Apply `edit` to the top-level map of JSON `raw`, then serialize it
+    /// again.
+    fn edit(
+        &self,
+        raw: &str,
+        edit: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+    ) -> anyhow::Result<String> {
+        let mut map: serde_json::Map<String, serde_json::Value> = self
+            .deserialize(raw, |_| ())
+            .map_err(|err| anyhow::anyhow!("{}", err))?;
+
+        edit(&mut map);
+        self.serialize(&map)
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = match self {
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+            Format::Json => "JSON",
+        };
+        write!(f, "{}", format)
+    }
+}
+
+/// See `Format::append_to_list`, TOML is edited with `toml_edit`, keeping
+/// comments & formatting.
+///
+/// * `raw`: &str
+/// * `key`: &str
+/// * `item`: &impl serde::Serialize
+fn append_to_toml_list(
+    raw: &str,
+    key: &str,
+    item: &impl serde::Serialize,
+) -> anyhow::Result<String> {
+    use toml_edit::{ArrayOfTables, Item, Value};
+
+    let mut doc: toml_edit::DocumentMut = raw.parse()?;
+    let mut value = item.serialize(toml_edit::ser::ValueSerializer::new())?;
+    match doc.get_mut(key) {
+        Some(Item::ArrayOfTables(tables)) => {
+            let Value::InlineTable(table) = value else {
+                anyhow::bail!("Items of `{}` must be tables", key);
+            };
+            tables.push(table.into_table());
+        }
+        Some(Item::Value(Value::Array(items))) => {
+            // Laid out like the last item, e.g. on its own line.
+            if let Some(last) = items.iter().last() {
+                *value.decor_mut() = last.decor().clone();
+            }
+            items.push_formatted(value);
+        }
+        Some(_) => anyhow::bail!("`{}` isn't a list", key),
+        None => {
+            doc[key] = match value {
+                Value::InlineTable(table) => {
+                    Item::ArrayOfTables(ArrayOfTables::from_iter([table.into_table()]))
+                }
+                value => toml_edit::value(toml_edit::Array::from_iter([value])),
+            };
+        }
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Remove items @ `indices` of top-level list `key` in TOML `raw`, see
+/// `append_to_toml_list`.
+///
+/// * `raw`: &str
+/// * `key`: &str
+/// * `indices`: &[usize], in ascending order.
+fn remove_from_toml_list(raw: &str, key: &str, indices: &[usize]) -> anyhow::Result<String> {
+    use toml_edit::{Item, Value};
+
+    let mut doc: toml_edit::DocumentMut = raw.parse()?;
+    for &i in indices.iter().rev() {
+        match doc.get_mut(key) {
+            Some(Item::ArrayOfTables(tables)) => tables.remove(i),
+            Some(Item::Value(Value::Array(items))) => {
+                items.remove(i);
+            }
+            _ => (),
+        }
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Byte offset of the value @ `path` in TOML `raw`, or of its key, see
+/// `Format::locate`.
+///
+/// * `raw`: &str
+/// * `path`: &[&str]
+fn locate_toml(raw: &str, path: &[&str]) -> Option<usize> {
+    let doc = toml_edit::ImDocument::parse(raw).ok()?;
+    let mut item = doc.as_item();
+    let mut span = None;
+    for key in path {
+        (span, item) = match item.as_table_like() {
+            Some(table) => {
+                let (key, item) = table.get_key_value(key)?;
+                (key.span(), item)
+            }
+            None => {
+                let item = item.get(key.parse::<usize>().ok()?)?;
+                (item.span(), item)
+            }
+        };
+    }
+
+    span.map(|span| span.start)
+}
+
+/// Line & column, both starting at 1, of byte `offset` in `raw`.
+///
+/// * `raw`: &str
+/// * `offset`: usize
+fn line_column(raw: &str, offset: usize) -> (usize, usize) {
+    let before = &raw[..offset.min(raw.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+/// Errors may append their location to their message, we keep it apart.
+///
+/// * `message`: String
+/// * `location`: Option<(usize, usize)>
+pub fn strip_location(message: String, location: Option<(usize, usize)>) -> String {
+    let Some((line, column)) = location else {
+        return message;
+    };
+
+    let suffix = format!(" at line {} column {}", line, column);
+    match message.strip_suffix(&suffix) {
+        Some(stripped) => stripped.to_string(),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Format;
+    use crate::models::{
+        config::{get_sample, Config},
+        git_repo::GitRepo,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn round_trip_every_format() {
+        for format in Format::ALL {
+            let raw = format.serialize(&get_sample()).unwrap();
+            let config: Config = format.deserialize(&raw, |_| ()).unwrap();
+            assert_eq!(config.git_repos[0].path, Path::new(".config/nvim"));
+
+            let raw = format.append_to_list(&raw, "sources", &".zshrc").unwrap();
+            let (raw, removed) = format
+                .remove_from_list(&raw, "sources", |v| v.as_str() == Some(".config/nedots"))
+                .unwrap();
+            assert_eq!(removed, 1);
+
+            let config: Config = format.deserialize(&raw, |_| ()).unwrap();
+            assert_eq!(config.sources, [Path::new(".zshrc")]);
+        }
+    }
+
+    #[test]
+    fn toml_comments_survive_edits() {
+        let raw = "#:schema nedots.schema.json
+remote = \"x\" # Where dots are pushed
+sources = [
+  # Shells
+  \".bashrc\",
+  \".zshrc\",
+]
+
+# Editors
+[[git_repos]]
+remote = \"y\"
+path = \".config/nvim\"
+";
+        let format = Format::Toml;
+        let raw = format.append_to_list(raw, "sources", &".profile").unwrap();
+        let repo = GitRepo::new("z", Path::new(".emacs.d"));
+        let raw = format.append_to_list(&raw, "git_repos", &repo).unwrap();
+        let (raw, removed) = format
+            .remove_from_list(&raw, "sources", |v| v.as_str() == Some(".zshrc"))
+            .unwrap();
+        assert_eq!(removed, 1);
+
+        assert!(raw.starts_with("#:schema nedots.schema.json\nremote = \"x\" # Where dots"));
+        assert!(raw.contains("sources = [\n  # Shells\n  \".bashrc\",\n  \".profile\",\n]"));
+        assert!(raw.contains("# Editors\n[[git_repos]]"));
+
+        let config: Config = format.deserialize(&raw, |_| ()).unwrap();
+        assert_eq!(config.git_repos[1].path, Path::new(".emacs.d"));
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let err = Format::Toml
+            .deserialize::<Config>("remote = \"x\"\nsources = 1\n", |_| ())
+            .unwrap_err();
+        assert_eq!((err.line, err.column), (Some(2), Some(11)));
+
+        let err = Format::Json
+            .deserialize::<Config>("{\n  \"sources\": 1\n}", |_| ())
+            .unwrap_err();
+        assert_eq!((err.line, err.column), (Some(2), Some(14)));
+        assert_eq!(Format::from_path(Path::new("nedots.json")), Format::Json);
+    }
+
+    #[test]
+    fn every_format_is_located() {
+        let path = ["git_repos", "1", "dpeth"];
+
+        let toml = "remote = \"x\"
+sources = [\".a\", \".b\"]
+
+[[git_repos]]
+remote = \"y\"
+
+[[git_repos]]
+remote = \"z\"
+dpeth = 1
+";
+        assert_eq!(Format::Toml.locate(toml, &["sources", "1"]), Some((2, 18)));
+        assert_eq!(Format::Toml.locate(toml, &path), Some((9, 1)));
+
+        let json = r#"{
+  "remote": "x, }",
+  "sources": [".a", ".b"],
+  "git_repos": [
+    { "remote": "y" },
+    { "remote": "z", "dpeth": 1 }
+  ]
+}"#;
+        assert_eq!(Format::Json.locate(json, &["sources", "1"]), Some((3, 21)));
+        assert_eq!(Format::Json.locate(json, &path), Some((6, 22)));
+        assert_eq!(Format::Json.locate(json, &["sources", "2"]), None);
+    }
+}
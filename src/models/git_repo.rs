@@ -1,9 +1,166 @@
+use super::hooks::Hooks;
+use crate::errors::Error;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+/// Substrings of `git` errors that indicate the network, or the host, couldn't
+/// be reached. Anything else, e.g. being refused access or a mistyped remote,
+/// won't be fixed by trying again later.
+const UNREACHABLE: &[&str] = &[
+    "Could not resolve host",
+    "Connection refused",
+    "timed out",
+    "Network is unreachable",
+    "No route to host",
+];
+
+/// Substrings of `git` errors that indicate a mirror isn't there, e.g. on a
+/// drive that isn't mounted. Unlike `remote`, a missing mirror is expected to
+/// come back, so it's unreachable rather than an error.
+const MIRROR_MISSING: &[&str] = &[
+    "does not appear to be a git repository",
+    "Could not read from remote repository",
+];
+
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct GitRepo {
+    /// Remote git repository.
     pub remote: String,
+    /// Local path, relative to $HOME or absolute.
     pub path: PathBuf,
+
+    /// Additional remotes that are pushed to, and pulled from when `remote` is
+    /// unreachable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    /// Create a shallow clone, truncated to this many commits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+
+    /// Create a partial clone, omitting objects until they're needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<CloneFilter>,
+
+    /// Run around `install` & `sync` of this repository, see `Hooks`.
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+}
+
+/// Partial clone filters, see `git help clone`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CloneFilter {
+    /// Omit all blobs, they're fetched on checkout.
+    Blobless,
+    /// Omit all trees & blobs, they're fetched on checkout.
+    Treeless,
+}
+
+impl CloneFilter {
+    pub fn as_arg(&self) -> &str {
+        match self {
+            CloneFilter::Blobless => "--filter=blob:none",
+            CloneFilter::Treeless => "--filter=tree:0",
+        }
+    }
+}
+
+/// Where a `GitRepo` stands relative to its upstream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct RepoStatus {
+    /// Uncommitted or untracked changes in the working tree.
+    pub dirty: bool,
+    /// Commits we have that upstream doesn't.
+    pub ahead: usize,
+    /// Commits upstream has that we don't.
+    pub behind: usize,
+    /// On a branch with commits but no upstream, so none of it is pushed.
+    pub unpublished: bool,
+}
+
+impl RepoStatus {
+    /// Parse the output of `git status --porcelain=v2 --branch`.
+    ///
+    /// * `porcelain`: &str, raw output.
+    pub fn parse(porcelain: &str) -> Self {
+        let mut status = Self::default();
+        let (mut has_commits, mut on_branch, mut has_upstream) = (false, false, false);
+        for line in porcelain.lines() {
+            if let Some(oid) = line.strip_prefix("# branch.oid ") {
+                has_commits = oid != "(initial)";
+            } else if let Some(head) = line.strip_prefix("# branch.head ") {
+                on_branch = head != "(detached)";
+            } else if line.starts_with("# branch.upstream ") {
+                has_upstream = true;
+            } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+                has_upstream = true;
+                for count in ab.split_whitespace() {
+                    if let Some(ahead) = count.strip_prefix('+') {
+                        status.ahead = ahead.parse().unwrap_or_default();
+                    } else if let Some(behind) = count.strip_prefix('-') {
+                        status.behind = behind.parse().unwrap_or_default();
+                    }
+                }
+            } else if !line.starts_with('#') && !line.is_empty() {
+                status.dirty = true;
+            }
+        }
+
+        status.unpublished = has_commits && on_branch && !has_upstream;
+        status
+    }
+
+    pub fn is_diverged(&self) -> bool {
+        self.ahead > 0 && self.behind > 0
+    }
+
+    /// Whether there are commits that upstream doesn't have, or no upstream.
+    pub fn has_unpushed(&self) -> bool {
+        self.ahead > 0 || self.unpublished
+    }
+
+    /// Whether there's anything to commit, pull or (if we're allowed to) push.
+    ///
+    /// * `push`: bool, if we won't be pushing, being ahead doesn't count.
+    pub fn needs_sync(&self, push: bool) -> bool {
+        self.dirty || self.behind > 0 || (push && self.has_unpushed())
+    }
+}
+
+impl std::fmt::Display for RepoStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if self.dirty {
+            parts.push("dirty".to_string());
+        }
+
+        if self.unpublished {
+            parts.push("unpublished".to_string());
+        } else if self.is_diverged() {
+            parts.push(format!("diverged (+{}/-{})", self.ahead, self.behind));
+        } else if self.ahead > 0 {
+            parts.push(format!("ahead {}", self.ahead));
+        } else if self.behind > 0 {
+            parts.push(format!("behind {}", self.behind));
+        }
+
+        if parts.is_empty() {
+            write!(f, "up to date")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
 }
 
 impl GitRepo {
@@ -11,21 +168,164 @@ impl GitRepo {
         Self {
             remote: remote.to_string(),
             path: path.to_path_buf(),
+            ..Default::default()
         }
     }
 
+    /// `--depth` & `--filter` args, if we're to make a shallow or partial
+    /// clone.
+    fn clone_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(depth) = self.depth {
+            args.push(format!("--depth={}", depth));
+        }
+
+        if let Some(filter) = self.filter {
+            args.push(filter.as_arg().to_string());
+        }
+
+        args
+    }
+
     fn run_cmd(&self, args: &[&str]) -> anyhow::Result<()> {
-        crate::utils::run_cmd("git", args)
+        crate::utils::run_cmd("git", args).map_err(git_error)
+    }
+
+    fn run_cmd_output(&self, args: &[&str]) -> anyhow::Result<String> {
+        crate::utils::run_cmd_output("git", args).map_err(git_error)
     }
 
+    /// Run a `git` command that talks to `remote`. If it fails because
+    /// `remote` is unreachable, or is a mirror that's missing,
+    /// `Error::Unreachable` is returned.
+    ///
+    /// * `remote`: &str, the remote we're talking to.
+    /// * `args`: &[&str]
+    fn run_remote_cmd(&self, remote: &str, args: &[&str]) -> anyhow::Result<()> {
+        let is_mirror = remote != self.remote;
+        self.run_cmd(args)
+            .map_err(|err| match err.downcast_ref::<Error>() {
+                Some(Error::Git { stderr, .. })
+                    if !stderr.contains("Permission denied")
+                        && (UNREACHABLE.iter().any(|msg| stderr.contains(msg))
+                            || (is_mirror
+                                && MIRROR_MISSING.iter().any(|msg| stderr.contains(msg)))) =>
+                {
+                    Error::Unreachable(remote.to_string()).into()
+                }
+                _ => err,
+            })
+    }
+
+    /// Whether `err` was caused by an unreachable remote.
+    ///
+    /// * `err`: &anyhow::Error
+    pub fn is_unreachable(err: &anyhow::Error) -> bool {
+        matches!(err.downcast_ref::<Error>(), Some(Error::Unreachable(_)))
+    }
+
+    /// Name of the `git remote` for each of `mirrors`, paired with its
+    /// address.
+    fn mirror_remotes(&self) -> impl Iterator<Item = (String, &str)> {
+        self.mirrors
+            .iter()
+            .enumerate()
+            .map(|(i, mirror)| (format!("mirror-{}", i + 1), mirror.as_str()))
+    }
+
+    /// Try `func` with `remote`, then each of `mirrors` in turn, for as long as
+    /// they're unreachable.
+    ///
+    /// * `func`: called with the name of the `git remote` (`None` meaning the
+    ///   default) & its address.
+    fn with_fallback(
+        &self,
+        func: impl Fn(Option<&str>, &str) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let mut res = func(None, &self.remote);
+        for (name, mirror) in self.mirror_remotes() {
+            match &res {
+                Err(err) if Self::is_unreachable(err) => {
+                    log::debug!("{}, falling back to `{}`", err, mirror);
+                    res = func(Some(&name), mirror);
+                }
+                _ => break,
+            }
+        }
+
+        res
+    }
+
+    /// Register `mirrors` as remotes named `mirror-{n}`, or update them if
+    /// they've changed.
+    pub fn add_mirrors(&self) -> anyhow::Result<()> {
+        let path = self.path.display().to_string();
+        let remotes = self.run_cmd_output(&["-C", &path, "remote"])?;
+        for (name, mirror) in self.mirror_remotes() {
+            match remotes.lines().any(|remote| remote == name) {
+                true => self.run_cmd(&["-C", &path, "remote", "set-url", &name, mirror])?,
+                false => self.run_cmd(&["-C", &path, "remote", "add", &name, mirror])?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Clone from `remote`, or from a mirror if `remote` is unreachable.
+    /// Either way, `origin` will point at `remote`. Use `Clone::clone` for a
+    /// copy of `GitRepo`.
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> anyhow::Result<()> {
-        self.run_cmd(&["clone", &self.remote, &self.path.display().to_string()])
+        let path = self.path.display().to_string();
+        let clone_args = self.clone_args();
+
+        self.with_fallback(|_, remote| {
+            let mut args = vec!["clone"];
+            args.extend(clone_args.iter().map(String::as_str));
+            args.extend([remote, &path]);
+            self.run_remote_cmd(remote, &args)
+        })?;
+
+        if !self.mirrors.is_empty() {
+            self.run_cmd(&["-C", &path, "remote", "set-url", "origin", &self.remote])?;
+            self.add_mirrors()?;
+        }
+
+        Ok(())
     }
 
     pub fn add(&self, pattern: &str) -> anyhow::Result<()> {
         self.run_cmd(&["-C", &self.path.display().to_string(), "add", pattern])
     }
 
+    /// Whether anything @ `path` is tracked by this repository.
+    ///
+    /// * `path`: &Path
+    pub fn is_tracked(&self, path: &Path) -> bool {
+        self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "ls-files",
+            "--",
+            &path.display().to_string(),
+        ])
+        .is_ok_and(|out| !out.is_empty())
+    }
+
+    /// Move `from` to `to`, both within this repository, updating submodules.
+    ///
+    /// * `from`: &Path
+    /// * `to`: &Path
+    pub fn mv(&self, from: &Path, to: &Path) -> anyhow::Result<()> {
+        self.run_cmd(&[
+            "-C",
+            &self.path.display().to_string(),
+            "mv",
+            &from.display().to_string(),
+            &to.display().to_string(),
+        ])
+    }
+
     pub fn commit(&self, msg: &str) -> anyhow::Result<()> {
         let res = self.run_cmd(&["-C", &self.path.display().to_string(), "commit", "-m", msg]);
         if res.is_err() {
@@ -35,22 +335,465 @@ impl GitRepo {
         Ok(())
     }
 
+    /// Whether `path` has uncommitted or untracked changes.
+    ///
+    /// * `path`: &Path
+    pub fn is_changed(&self, path: &Path) -> bool {
+        self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "status",
+            "--porcelain",
+            "--",
+            &path.display().to_string(),
+        ])
+        .is_ok_and(|out| !out.is_empty())
+    }
+
+    /// Push to `remote` & all `mirrors`. Every remote is attempted. Once
+    /// `remote` has the push, mirrors that fail are only warned about,
+    /// otherwise `Error::Unreachable` is returned if `remote` was unreachable,
+    /// unless a mirror failed for another reason.
     pub fn push(&self) -> anyhow::Result<()> {
-        self.run_cmd(&["-C", &self.path.display().to_string(), "push"])
+        let path = self.path.display().to_string();
+        // A new branch has nowhere to push to until its upstream is set.
+        let mut args = vec!["-C", &path, "push"];
+        if self.status()?.unpublished {
+            args.extend(["--set-upstream", "origin", "HEAD"]);
+        }
+        let mut res = self.run_remote_cmd(&self.remote, &args);
+
+        for (name, mirror) in self.mirror_remotes() {
+            let mirror_res = self.run_remote_cmd(mirror, &["-C", &path, "push", &name, "HEAD"]);
+            match (&res, mirror_res) {
+                (Ok(_), Err(err)) => {
+                    log::warn!("⚠️ Failed to push to mirror `{}`, {}", mirror, err)
+                }
+                (Err(unreachable), Err(err))
+                    if Self::is_unreachable(unreachable) && !Self::is_unreachable(&err) =>
+                {
+                    res = Err(err)
+                }
+                _ => (),
+            }
+        }
+
+        res
     }
 
     pub fn init_submodules(&self) -> anyhow::Result<()> {
+        let path = self.path.display().to_string();
+        let clone_args = self.clone_args();
+
+        let mut args = vec!["-C", &path, "submodule", "update", "--init", "--recursive"];
+        args.extend(clone_args.iter().map(String::as_str));
+        self.run_cmd(&args)
+    }
+
+    /// Pull from `remote`, or from a mirror if `remote` is unreachable. Shallow
+    /// clones are kept at `depth`, rather than pulling in history that was
+    /// intentionally left out.
+    pub fn pull(&self) -> anyhow::Result<()> {
+        let path = self.path.display().to_string();
+        let shallow = self.is_shallow();
+        let depth = match (shallow, self.depth) {
+            (true, Some(depth)) => Some(format!("--depth={}", depth)),
+            _ => None,
+        };
+
+        let res = self.with_fallback(|name, remote| {
+            let mut args = vec!["-C", &path, "pull"];
+            args.extend(depth.as_deref());
+
+            match name {
+                Some(name) => {
+                    let branch = self.branch()?;
+                    self.run_remote_cmd(remote, &[&args[..], &[name, &branch]].concat())
+                }
+                None => self.run_remote_cmd(remote, &args),
+            }
+        });
+
+        res.map_err(|err| match !shallow || Self::is_unreachable(&err) {
+            true => err,
+            false => err.context(format!(
+                "`{}` is a shallow clone, try again with `--unshallow`",
+                path
+            )),
+        })
+    }
+
+    pub fn branch(&self) -> anyhow::Result<String> {
+        self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "rev-parse",
+            "--abbrev-ref",
+            "HEAD",
+        ])
+    }
+
+    pub fn is_shallow(&self) -> bool {
+        self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "rev-parse",
+            "--is-shallow-repository",
+        ])
+        .map(|out| out == "true")
+        .unwrap_or_default()
+    }
+
+    /// Fetch the complete history of a shallow clone.
+    pub fn unshallow(&self) -> anyhow::Result<()> {
+        self.run_remote_cmd(
+            &self.remote,
+            &[
+                "-C",
+                &self.path.display().to_string(),
+                "fetch",
+                "--unshallow",
+            ],
+        )
+    }
+
+    /// Address of `origin`.
+    pub fn origin_url(&self) -> anyhow::Result<String> {
+        self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "remote",
+            "get-url",
+            "origin",
+        ])
+    }
+
+    pub fn head(&self) -> anyhow::Result<String> {
+        self.run_cmd_output(&["-C", &self.path.display().to_string(), "rev-parse", "HEAD"])
+    }
+
+    /// Check out `rev`. When on a branch that `rev` is ahead of, the branch is
+    /// fast-forwarded so that upstream tracking is kept. Otherwise `HEAD` is
+    /// detached at `rev`, so that commits on the branch aren't lost.
+    ///
+    /// * `rev`: &str, commit to check out.
+    pub fn checkout(&self, rev: &str) -> anyhow::Result<()> {
+        let path = self.path.display().to_string();
+        let branch = self.branch()?;
+        if branch != "HEAD" && self.is_ancestor("HEAD", rev) {
+            return self.run_cmd(&["-C", &path, "merge", "--ff-only", "--quiet", rev]);
+        }
+
+        if branch != "HEAD" {
+            log::warn!(
+                "⚠️ `{}` has commits that aren't in {}, so it's left as it is & `HEAD` is detached",
+                branch,
+                rev
+            );
+        }
+        self.run_cmd(&["-C", &path, "checkout", "--quiet", "--detach", rev])
+    }
+
+    /// Whether `ancestor` is `rev`, or one of its ancestors.
+    ///
+    /// * `ancestor`: &str
+    /// * `rev`: &str
+    fn is_ancestor(&self, ancestor: &str, rev: &str) -> bool {
+        self.run_cmd(&[
+            "-C",
+            &self.path.display().to_string(),
+            "merge-base",
+            "--is-ancestor",
+            ancestor,
+            rev,
+        ])
+        .is_ok()
+    }
+
+    /// Whether `path`, relative to this repository, is registered as a
+    /// submodule.
+    ///
+    /// * `path`: &Path, relative path of submodule.
+    pub fn has_submodule(&self, path: &Path) -> bool {
+        self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "ls-files",
+            "--stage",
+            "--",
+            &path.display().to_string(),
+        ])
+        .map(|out| out.starts_with("160000"))
+        .unwrap_or_default()
+    }
+
+    pub fn add_submodule(&self, remote: &str, path: &Path) -> anyhow::Result<()> {
         self.run_cmd(&[
             "-C",
             &self.path.display().to_string(),
             "submodule",
-            "update",
-            "--init",
-            "--recursive",
+            "add",
+            remote,
+            &path.display().to_string(),
         ])
     }
 
-    pub fn pull(&self) -> anyhow::Result<()> {
-        self.run_cmd(&["-C", &self.path.display().to_string(), "pull"])
+    /// The commit recorded for submodule @ `path` in `HEAD`.
+    ///
+    /// * `path`: &Path, relative path of submodule.
+    pub fn submodule_commit(&self, path: &Path) -> anyhow::Result<String> {
+        self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "rev-parse",
+            &format!("HEAD:{}", path.display()),
+        ])
+    }
+
+    /// Fetch `HEAD` of another local repository @ `from`.
+    ///
+    /// * `from`: &Path
+    pub fn fetch_from(&self, from: &Path) -> anyhow::Result<()> {
+        self.run_cmd(&[
+            "-C",
+            &self.path.display().to_string(),
+            "fetch",
+            &from.display().to_string(),
+            "HEAD",
+        ])
+    }
+
+    pub fn fetch(&self) -> anyhow::Result<()> {
+        self.run_remote_cmd(
+            &self.remote,
+            &["-C", &self.path.display().to_string(), "fetch"],
+        )
+    }
+
+    /// Compare the working tree & `HEAD` with upstream, as of the last
+    /// `fetch`.
+    pub fn status(&self) -> anyhow::Result<RepoStatus> {
+        let porcelain = self.run_cmd_output(&[
+            "-C",
+            &self.path.display().to_string(),
+            "status",
+            "--porcelain=v2",
+            "--branch",
+        ])?;
+
+        Ok(RepoStatus::parse(&porcelain))
+    }
+}
+
+/// `Error::Command` of `git`, as `Error::Git`.
+///
+/// * `err`: anyhow::Error
+fn git_error(err: anyhow::Error) -> anyhow::Error {
+    match err.downcast::<Error>() {
+        Ok(Error::Command { cmd, code, stderr }) => Error::Git { cmd, code, stderr }.into(),
+        Ok(err) => err.into(),
+        Err(err) => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CloneFilter, GitRepo, RepoStatus};
+    use crate::utils::testing::{bare_repo, commit_files, git, git_output, head, init_repo};
+    use std::path::Path;
+
+    #[test]
+    fn unreachable_remote_is_detected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let bare = dir.join("remote.git");
+        std::fs::create_dir_all(&bare).expect("failed to create remote.git");
+        git(&bare, &["init", "--bare", "--quiet"]);
+
+        let repo = GitRepo::new(&bare.display().to_string(), &dir.join("local"));
+        assert!(repo.clone().is_ok());
+        git(&repo.path, &["config", "user.name", "nedots"]);
+        git(&repo.path, &["config", "user.email", "nedots@localhost"]);
+        git(
+            &repo.path,
+            &["commit", "--allow-empty", "--quiet", "-m", "Test"],
+        );
+
+        // `.invalid` never resolves, as though offline.
+        let set_url = |url: &str| git(&repo.path, &["remote", "set-url", "origin", url]);
+        set_url("https://nedots.invalid/remote.git");
+        let err = repo.push().expect_err("push should fail");
+        assert!(GitRepo::is_unreachable(&err));
+
+        // A mistyped remote won't be reached by trying again.
+        set_url(&dir.join("typo.git").display().to_string());
+        let err = repo.push().expect_err("push should fail");
+        assert!(!GitRepo::is_unreachable(&err));
+
+        set_url(&bare.display().to_string());
+        assert!(repo.push().is_ok());
+        assert!(repo.fetch().is_ok());
+    }
+
+    #[test]
+    fn mirrors_are_fallen_back_on_and_pushed_to() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let remote = bare_repo(dir, "remote", &[("a.conf", "1")]);
+        let mirror = dir.join("mirror.git");
+        git(
+            dir,
+            &["clone", "--quiet", "--bare", "remote.git", "mirror.git"],
+        );
+
+        // `remote` is unreachable, so it's cloned from the mirror.
+        let repo = GitRepo {
+            mirrors: vec![mirror.display().to_string()],
+            ..GitRepo::new("https://nedots.invalid/remote.git", &dir.join("local"))
+        };
+        repo.clone().unwrap();
+        assert_eq!(
+            git_output(&repo.path, &["remote", "get-url", "origin"]),
+            repo.remote
+        );
+        assert_eq!(
+            git_output(&repo.path, &["remote", "get-url", "mirror-1"]),
+            repo.mirrors[0]
+        );
+
+        // Commits still reach the mirror, though `remote` is unreachable.
+        git(&repo.path, &["config", "user.name", "nedots"]);
+        git(&repo.path, &["config", "user.email", "nedots@localhost"]);
+        commit_files(&repo.path, &[("a.conf", "2")]);
+        let err = repo.push().expect_err("push should fail");
+        assert!(GitRepo::is_unreachable(&err));
+        assert_eq!(head(&mirror), head(&repo.path));
+
+        // Once `remote` is reachable, both are pushed to.
+        let remote = remote.display().to_string();
+        git(&repo.path, &["remote", "set-url", "origin", &remote]);
+        git(
+            &repo.path,
+            &["push", "--quiet", "--set-upstream", "origin", "main"],
+        );
+        commit_files(&repo.path, &[("a.conf", "3")]);
+        let repo = GitRepo { remote, ..repo };
+        repo.push().unwrap();
+        assert_eq!(head(Path::new(&repo.remote)), head(&repo.path));
+        assert_eq!(head(&mirror), head(&repo.path));
+    }
+
+    #[test]
+    fn unpublished_branch_is_pushed_with_upstream() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let remote = dir.join("remote.git");
+        git(
+            dir,
+            &[
+                "init",
+                "--quiet",
+                "--bare",
+                "--initial-branch=main",
+                "remote.git",
+            ],
+        );
+
+        let repo = GitRepo::new(&remote.display().to_string(), &dir.join("local"));
+        init_repo(&repo.path);
+        commit_files(&repo.path, &[("a.conf", "1")]);
+        git(&repo.path, &["remote", "add", "origin", &repo.remote]);
+        assert!(repo.status().unwrap().unpublished);
+
+        repo.push().unwrap();
+        assert_eq!(head(&remote), head(&repo.path));
+        assert_eq!(repo.status().unwrap(), RepoStatus::default());
+    }
+
+    #[test]
+    fn missing_mirror_doesnt_fail_push() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let remote = bare_repo(dir, "remote", &[("a.conf", "1")]);
+
+        let repo = GitRepo {
+            mirrors: vec![dir.join("unmounted/dots.git").display().to_string()],
+            ..GitRepo::new(&remote.display().to_string(), &dir.join("local"))
+        };
+        repo.clone().unwrap();
+        repo.add_mirrors().unwrap();
+        git(&repo.path, &["config", "user.name", "nedots"]);
+        git(&repo.path, &["config", "user.email", "nedots@localhost"]);
+        commit_files(&repo.path, &[("a.conf", "2")]);
+
+        repo.push().unwrap();
+        assert_eq!(head(&remote), head(&repo.path));
+
+        // With `remote` unreachable too, there's nowhere the push went.
+        let unreachable = "https://nedots.invalid/remote.git";
+        git(&repo.path, &["remote", "set-url", "origin", unreachable]);
+        let repo = GitRepo {
+            remote: unreachable.to_string(),
+            ..repo
+        };
+        let err = repo.push().expect_err("push should fail");
+        assert!(GitRepo::is_unreachable(&err));
+    }
+
+    #[test]
+    fn shallow_partial_clone_args() {
+        let repo = GitRepo {
+            depth: Some(1),
+            filter: Some(CloneFilter::Blobless),
+            ..Default::default()
+        };
+
+        assert_eq!(repo.clone_args(), ["--depth=1", "--filter=blob:none"]);
+        assert!(GitRepo::default().clone_args().is_empty());
+    }
+
+    #[test]
+    fn parse_clean_status() {
+        let status = RepoStatus::parse(
+            "# branch.oid 0a1b2c\n# branch.head main\n# branch.upstream origin/main\n# branch.ab +0 -0",
+        );
+
+        assert_eq!(status, RepoStatus::default());
+        assert!(!status.needs_sync(true));
+    }
+
+    #[test]
+    fn parse_dirty_diverged_status() {
+        let status = RepoStatus::parse(
+            "# branch.head main\n# branch.ab +2 -1\n1 .M N... 100644 100644 100644 a b .bashrc\n? new",
+        );
+
+        assert!(status.dirty);
+        assert!(status.is_diverged());
+        assert_eq!(status.to_string(), "dirty, diverged (+2/-1)");
+    }
+
+    #[test]
+    fn ahead_only_needs_sync_when_pushing() {
+        let status = RepoStatus {
+            ahead: 1,
+            ..Default::default()
+        };
+
+        assert!(status.needs_sync(true));
+        assert!(!status.needs_sync(false));
+    }
+
+    #[test]
+    fn branch_without_upstream_is_unpublished() {
+        let status = RepoStatus::parse("# branch.oid 0a1b2c\n# branch.head main");
+        assert!(status.unpublished);
+        assert!(status.needs_sync(true));
+        assert!(!status.needs_sync(false));
+        assert_eq!(status.to_string(), "unpublished");
+
+        // Nothing to push without commits, or without a branch to push.
+        assert!(!RepoStatus::parse("# branch.oid (initial)\n# branch.head main").unpublished);
+        assert!(!RepoStatus::parse("# branch.oid 0a1b2c\n# branch.head (detached)").unpublished);
     }
 }
@@ -0,0 +1,209 @@
+//! Shell commands run before & after `install`, `gather`, `sync` & `backup`,
+//! e.g. to reload a window manager once its config is installed.
+//!
+//! Global `hooks` run once around the whole operation, those of a source or
+//! git repository around that source or repository alone. Each is run with
+//! `sh -c`, & the following environment variables:
+//!
+//! * `NEDOTS_HOOK`: name of the hook, e.g. `post_install`.
+//! * `NEDOTS_ROOT` & `NEDOTS_DOTS_DIR`: see `Config`.
+//! * `NEDOTS_SRC` & `NEDOTS_DST`: what's copied from & to. Only set for hooks
+//!   of a source or git repository. For a git repository they're always its
+//!   `remote` & `path`, whether it's cloned, or synced in either direction.
+//! * `NEDOTS_CHANGED`: `1` if anything changed, else `0`. Only set for
+//!   `post_*` hooks.
+
+use crate::utils;
+use anyhow::Context;
+use std::ffi::OsString;
+
+/// Environment variables of a hook, see above.
+pub type Env = Vec<(&'static str, OsString)>;
+
+/// Operations that hooks may be run around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Install,
+    Gather,
+    Sync,
+    Backup,
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let event = match self {
+            Event::Install => "install",
+            Event::Gather => "gather",
+            Event::Sync => "sync",
+            Event::Backup => "backup",
+        };
+        write!(f, "{}", event)
+    }
+}
+
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[schemars(deny_unknown_fields)]
+pub struct Hooks {
+    /// Run before `install`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_install: Option<String>,
+    /// Run after `install`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_install: Option<String>,
+
+    /// Run before gathering, i.e. `sync --gather`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_gather: Option<String>,
+    /// Run after gathering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_gather: Option<String>,
+
+    /// Run before `sync`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_sync: Option<String>,
+    /// Run after `sync`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_sync: Option<String>,
+
+    /// Run before `backup`, or restoring a backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_backup: Option<String>,
+    /// Run after `backup`, or restoring a backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_backup: Option<String>,
+
+    /// Only run `post_*` hooks if something was actually copied, or a git
+    /// repository changed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub only_changed: bool,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        *self == Hooks::default()
+    }
+
+    /// Command of `event`, before or after it.
+    ///
+    /// * `event`: Event
+    /// * `post`: bool
+    fn get(&self, event: Event, post: bool) -> Option<&str> {
+        let (pre_cmd, post_cmd) = match event {
+            Event::Install => (&self.pre_install, &self.post_install),
+            Event::Gather => (&self.pre_gather, &self.post_gather),
+            Event::Sync => (&self.pre_sync, &self.post_sync),
+            Event::Backup => (&self.pre_backup, &self.post_backup),
+        };
+
+        match post {
+            true => post_cmd.as_deref(),
+            false => pre_cmd.as_deref(),
+        }
+    }
+
+    /// Run the `pre_*` hook of `event`, if set.
+    ///
+    /// * `event`: Event
+    /// * `env`: &Env
+    pub fn pre(&self, event: Event, env: &Env) -> anyhow::Result<()> {
+        match self.get(event, false) {
+            Some(cmd) => run(&format!("pre_{}", event), cmd, env.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Run the `post_*` hook of `event`, if set, unless nothing changed &
+    /// `only_changed` is set.
+    ///
+    /// * `event`: Event
+    /// * `changed`: bool
+    /// * `env`: &Env
+    pub fn post(&self, event: Event, changed: bool, env: &Env) -> anyhow::Result<()> {
+        let Some(cmd) = self.get(event, true) else {
+            return Ok(());
+        };
+
+        let name = format!("post_{}", event);
+        if self.only_changed && !changed {
+            log::debug!("Skipping `{}` hook, nothing changed", name);
+            return Ok(());
+        }
+
+        let mut env = env.clone();
+        env.push(("NEDOTS_CHANGED", if changed { "1" } else { "0" }.into()));
+        run(&name, cmd, env)
+    }
+
+    /// Run `op`, between the `pre_*` & `post_*` hooks of `event`. `op`
+    /// returns whether it changed anything.
+    ///
+    /// * `event`: Event
+    /// * `env`: &Env
+    /// * `op`: impl FnOnce() -> anyhow::Result<bool>
+    pub fn around(
+        &self,
+        event: Event,
+        env: &Env,
+        op: impl FnOnce() -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        self.pre(event, env)?;
+        let changed = op()?;
+        self.post(event, changed, env)?;
+        Ok(changed)
+    }
+}
+
+/// * `name`: &str, e.g. `post_install`.
+/// * `cmd`: &str
+/// * `env`: Env
+fn run(name: &str, cmd: &str, mut env: Env) -> anyhow::Result<()> {
+    log::info!("🪝 {} `{}`", name, console::style(cmd).dim());
+
+    env.push(("NEDOTS_HOOK", name.into()));
+    let output = utils::run_cmd_env("sh", &["-c", cmd], env)
+        .with_context(|| format!("`{}` hook failed", name))?;
+
+    if !output.is_empty() {
+        log::info!("{}", output);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Env, Event, Hooks};
+
+    #[test]
+    fn post_hooks_may_only_run_when_changed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let log = dir.join("log");
+
+        let hooks = Hooks {
+            pre_install: Some(format!("echo \"$NEDOTS_HOOK\" >> {}", log.display())),
+            post_install: Some(format!(
+                "echo \"$NEDOTS_HOOK $NEDOTS_CHANGED $NEDOTS_DST\" >> {}",
+                log.display()
+            )),
+            only_changed: true,
+            ..Default::default()
+        };
+        let env: Env = vec![("NEDOTS_DST", "/dst".into())];
+
+        hooks.around(Event::Install, &env, || Ok(false)).unwrap();
+        hooks.around(Event::Install, &env, || Ok(true)).unwrap();
+        hooks.around(Event::Sync, &env, || Ok(true)).unwrap();
+
+        let log = std::fs::read_to_string(log).unwrap();
+        assert_eq!(log, "pre_install\npre_install\npost_install 1 /dst\n");
+
+        let failing = Hooks {
+            pre_sync: Some("exit 1".to_string()),
+            ..Default::default()
+        };
+        assert!(failing.around(Event::Sync, &env, || Ok(true)).is_err());
+    }
+}
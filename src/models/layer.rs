@@ -0,0 +1,380 @@
+//! `nedots.yml` may `include` other files, e.g. a shared team config, each is
+//! a `Layer` that's merged into a single `Config`.
+//!
+//! Files are merged depth first: the files a file includes, in the order they
+//! are listed, then the file itself. So a file overrides anything it includes.
+//!
+//! * `root`, `dots_dir`, `backup_dir`, `remote`, `submodules` & `hooks`: the
+//!   last file to set them wins.
+//! * `source_hooks`: merged, hooks of the same source replace earlier ones.
+//! * `mirrors`, `sources`, `ignore` & each list of `packages`: concatenated,
+//!   duplicates are dropped.
+//! * `git_repos`: concatenated, a repository with the same `path` as an
+//!   earlier one replaces it.
+//!
+//! A file that's included more than once is only merged the first time, and a
+//! file including itself, directly or otherwise, is an error.
+
+use super::{config::Config, format::Format, packages::Manager};
+use crate::errors::Error;
+use anyhow::Context;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// A single file, & the `Config` it holds on its own.
+#[derive(Debug)]
+pub struct Layer {
+    pub path: PathBuf,
+    pub format: Format,
+    pub raw: String,
+    pub config: Config,
+    /// Paths of unknown keys, e.g. `git_repos.0.remtoe`.
+    pub unknown: Vec<String>,
+    /// Top-level keys that are set, to tell `submodules: false` from unset.
+    keys: Vec<String>,
+}
+
+impl Layer {
+    /// Deserialize `raw`, read from `path`, in the format of its extension.
+    /// Unknown keys are collected rather than failing.
+    ///
+    /// * `path`: &Path
+    /// * `raw`: String
+    pub fn parse(path: &Path, raw: String) -> anyhow::Result<Layer> {
+        let format = Format::from_path(path);
+        let parse_err = |err| Error::Parse {
+            path: path.to_path_buf(),
+            err,
+        };
+
+        let mut unknown = Vec::new();
+        let config: Config = format
+            .deserialize(&raw, |p| unknown.push(p))
+            .map_err(parse_err)?;
+
+        let keys: BTreeMap<String, serde::de::IgnoredAny> =
+            format.deserialize(&raw, |_| ()).map_err(parse_err)?;
+
+        Ok(Layer {
+            path: path.to_path_buf(),
+            format,
+            raw,
+            config,
+            unknown,
+            keys: keys.into_keys().collect(),
+        })
+    }
+
+    fn is_set(&self, key: &str) -> bool {
+        self.keys.iter().any(|k| k == key)
+    }
+
+    /// Files matched by each of `include`, relative to this file. An entry may
+    /// be a file, a glob or a directory, e.g. `conf.d`, in which case every
+    /// YAML, TOML & JSON file in it is included in order of name.
+    fn includes(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let base = self.path.parent().unwrap_or(Path::new(""));
+
+        let mut paths = Vec::new();
+        for entry in &self.config.include {
+            let path = base.join(entry);
+
+            if path.is_dir() {
+                let mut files: Vec<PathBuf> = std::fs::read_dir(&path)?
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| {
+                        p.is_file()
+                            && p.extension().is_some_and(|ext| {
+                                Format::ALL
+                                    .iter()
+                                    .any(|f| f.extensions().iter().any(|e| ext == *e))
+                            })
+                    })
+                    .collect();
+                files.sort();
+                paths.extend(files);
+            } else if entry.contains(['*', '?', '[']) {
+                for file in glob::glob(&path.display().to_string())? {
+                    paths.push(file?);
+                }
+            } else if path.exists() {
+                paths.push(path);
+            } else {
+                return Err(Error::NotFound(path.display().to_string()))
+                    .with_context(|| format!("Failed to include `{}`", entry));
+            }
+        }
+
+        Ok(paths)
+    }
+}
+
+/// Read `path` & every file it includes, in the order they're to be merged.
+///
+/// * `path`: &Path
+pub fn load(path: &Path) -> anyhow::Result<Vec<Layer>> {
+    let mut layers = Vec::new();
+    load_into(path, &mut Vec::new(), &mut layers)?;
+    Ok(layers)
+}
+
+/// * `path`: &Path
+/// * `stack`: &mut Vec<PathBuf>, files currently being loaded, to find cycles.
+/// * `layers`: &mut Vec<Layer>, files loaded so far.
+fn load_into(path: &Path, stack: &mut Vec<PathBuf>, layers: &mut Vec<Layer>) -> anyhow::Result<()> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Failed to read `{}`", path.display()))?;
+
+    if stack.contains(&path) {
+        let chain: Vec<String> = stack
+            .iter()
+            .skip_while(|p| **p != path)
+            .chain([&path])
+            .map(|p| format!("`{}`", p.display()))
+            .collect();
+        return Err(Error::IncludeCycle(chain.join(" -> ")).into());
+    }
+
+    if layers.iter().any(|l| l.path == path) {
+        log::debug!("Already included `{}`", path.display());
+        return Ok(());
+    }
+
+    log::trace!("Reading `{}`...", path.display());
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read `{}`", path.display()))?;
+    let layer = Layer::parse(&path, raw)?;
+
+    stack.push(path);
+    for include in layer.includes()? {
+        load_into(&include, stack, layers)
+            .with_context(|| format!("Included by `{}`", layer.path.display()))?;
+    }
+    stack.pop();
+
+    layers.push(layer);
+    Ok(())
+}
+
+/// Which file each part of a merged `Config` came from. Lists are in the same
+/// order as their counterparts in `Config`.
+#[derive(Debug, Default)]
+pub struct Origins {
+    pub root: Option<PathBuf>,
+    pub dots_dir: Option<PathBuf>,
+    pub backup_dir: Option<PathBuf>,
+    pub remote: Option<PathBuf>,
+    pub submodules: Option<PathBuf>,
+    pub mirrors: Vec<PathBuf>,
+    pub sources: Vec<PathBuf>,
+    pub git_repos: Vec<PathBuf>,
+    pub ignore: Vec<PathBuf>,
+    /// Keyed by source, like `Config::source_hooks`.
+    pub source_hooks: BTreeMap<PathBuf, PathBuf>,
+    pub packages: BTreeMap<Manager, Vec<PathBuf>>,
+    pub hooks: Option<PathBuf>,
+}
+
+/// Merge `layers`, in order, into a single `Config`. `file` & `include` are
+/// those of the last layer, `nedots.yml` itself.
+///
+/// * `layers`: &[Layer]
+pub fn merge(layers: &[Layer]) -> (Config, Origins) {
+    let mut config = Config::default();
+    let mut origins = Origins::default();
+
+    for layer in layers {
+        let path = &layer.path;
+
+        if layer.is_set("root") {
+            config.root = layer.config.root.clone();
+            origins.root = Some(path.clone());
+        }
+
+        if layer.is_set("dots_dir") {
+            config.dots_dir = layer.config.dots_dir.clone();
+            origins.dots_dir = Some(path.clone());
+        }
+
+        if layer.is_set("backup_dir") {
+            config.backup_dir = layer.config.backup_dir.clone();
+            origins.backup_dir = Some(path.clone());
+        }
+
+        if layer.is_set("remote") {
+            config.remote = layer.config.remote.clone();
+            origins.remote = Some(path.clone());
+        }
+
+        if layer.is_set("submodules") {
+            config.submodules = layer.config.submodules;
+            origins.submodules = Some(path.clone());
+        }
+
+        for mirror in &layer.config.mirrors {
+            if !config.mirrors.contains(mirror) {
+                config.mirrors.push(mirror.clone());
+                origins.mirrors.push(path.clone());
+            }
+        }
+
+        for source in &layer.config.sources {
+            if !config.sources.contains(source) {
+                config.sources.push(source.clone());
+                origins.sources.push(path.clone());
+            }
+        }
+
+        for pattern in &layer.config.ignore {
+            if !config.ignore.contains(pattern) {
+                config.ignore.push(pattern.clone());
+                origins.ignore.push(path.clone());
+            }
+        }
+
+        for (source, hooks) in &layer.config.source_hooks {
+            config.source_hooks.insert(source.clone(), hooks.clone());
+            origins.source_hooks.insert(source.clone(), path.clone());
+        }
+
+        for (manager, packages) in &layer.config.packages {
+            let merged = config.packages.entry(*manager).or_default();
+            let from = origins.packages.entry(*manager).or_default();
+            for package in packages {
+                if !merged.contains(package) {
+                    merged.push(package.clone());
+                    from.push(path.clone());
+                }
+            }
+        }
+
+        if layer.is_set("hooks") {
+            config.hooks = layer.config.hooks.clone();
+            origins.hooks = Some(path.clone());
+        }
+
+        for repo in &layer.config.git_repos {
+            match config.git_repos.iter().position(|r| r.path == repo.path) {
+                Some(i) => {
+                    config.git_repos[i] = Clone::clone(repo);
+                    origins.git_repos[i] = path.clone();
+                }
+                None => {
+                    config.git_repos.push(Clone::clone(repo));
+                    origins.git_repos.push(path.clone());
+                }
+            }
+        }
+
+        config.file = path.clone();
+        config.include = layer.config.include.clone();
+    }
+
+    (config, origins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, Layer, Manager};
+    use std::path::{Path, PathBuf};
+
+    fn layer(name: &str, raw: &str) -> Layer {
+        Layer::parse(Path::new(name), raw.to_string()).unwrap()
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let team = layer(
+            "team.yml",
+            "remote: team
+submodules: true
+dots_dir: team-dots
+sources: [.bashrc, .profile]
+ignore: ['*.swp']
+source_hooks:
+  .bashrc:
+    post_install: echo
+  .profile:
+    post_install: echo
+packages:
+  apt: [git]
+hooks:
+  post_sync: echo
+git_repos:
+  - remote: team-nvim
+    path: .config/nvim
+",
+        );
+        let mine = layer(
+            "nedots.yml",
+            "include: [team.yml]
+submodules: false
+ignore: ['*.swp', '*.bak']
+source_hooks:
+  .profile:
+    post_install: echo
+packages:
+  apt: [git, zsh]
+sources: [.profile, .zshrc]
+git_repos:
+  - remote: my-nvim
+    path: .config/nvim
+",
+        );
+
+        let (config, origins) = merge(&[team, mine]);
+        assert_eq!(config.remote, "team");
+        assert!(!config.submodules);
+        assert_eq!(
+            config.sources,
+            [".bashrc", ".profile", ".zshrc"].map(PathBuf::from)
+        );
+        assert_eq!(config.git_repos.len(), 1);
+        assert_eq!(config.git_repos[0].remote, "my-nvim");
+
+        assert_eq!(origins.remote, Some("team.yml".into()));
+        assert_eq!(origins.dots_dir, Some("team.yml".into()));
+        assert_eq!(origins.root, None);
+        assert_eq!(
+            origins.sources,
+            ["team.yml", "team.yml", "nedots.yml"].map(PathBuf::from)
+        );
+        assert_eq!(origins.git_repos, [PathBuf::from("nedots.yml")]);
+        assert_eq!(
+            origins.ignore,
+            ["team.yml", "nedots.yml"].map(PathBuf::from)
+        );
+        assert_eq!(
+            origins.source_hooks.into_iter().collect::<Vec<_>>(),
+            [(".bashrc", "team.yml"), (".profile", "nedots.yml")]
+                .map(|(s, f)| (s.into(), f.into()))
+        );
+        assert_eq!(
+            origins.packages[&Manager::Apt],
+            ["team.yml", "nedots.yml"].map(PathBuf::from)
+        );
+        assert_eq!(origins.hooks, Some("team.yml".into()));
+        assert_eq!(config.file, Path::new("nedots.yml"));
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        std::fs::write(dir.join("nedots.yml"), "include: [conf.d]\nremote: x\n").unwrap();
+        std::fs::write(dir.join("conf.d/a.yml"), "sources: [.a]\n").unwrap();
+        std::fs::write(dir.join("conf.d/b.yml"), "include: [../nedots.yml]\n").unwrap();
+
+        let err = super::load(&dir.join("nedots.yml")).unwrap_err();
+        assert!(format!("{:#}", err).contains("Include cycle"));
+
+        std::fs::write(dir.join("conf.d/b.yml"), "include: [a.yml]\n").unwrap();
+        let layers = super::load(&dir.join("nedots.yml")).unwrap();
+        let names: Vec<_> = layers.iter().map(|l| l.path.file_name().unwrap()).collect();
+        assert_eq!(names, ["a.yml", "b.yml", "nedots.yml"]);
+    }
+}
@@ -1,2 +1,10 @@
 pub mod config;
+pub mod diagnostic;
+pub mod format;
 pub mod git_repo;
+pub mod hooks;
+pub mod layer;
+pub mod packages;
+pub mod report;
+pub mod state;
+pub mod status;
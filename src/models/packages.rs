@@ -0,0 +1,239 @@
+//! Programs that `sources` configure, installed by the package manager of each.
+//!
+//! Only packages installed explicitly are listed, so that a snapshot doesn't
+//! record every dependency of them too.
+//!
+//! System package managers, i.e. `apt`, `dnf` & `pacman`, install for every
+//! user, so they're run with `sudo` unless `nedots` is run as root. `sudo` may
+//! ask for a password on the terminal.
+
+use crate::utils;
+use std::ffi::OsStr;
+
+/// Package managers, as they're keyed in `packages`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Manager {
+    Apt,
+    Dnf,
+    Pacman,
+    Brew,
+    Cargo,
+    Pip,
+    Npm,
+    Flatpak,
+}
+
+impl Manager {
+    pub const ALL: [Manager; 8] = [
+        Manager::Apt,
+        Manager::Dnf,
+        Manager::Pacman,
+        Manager::Brew,
+        Manager::Cargo,
+        Manager::Pip,
+        Manager::Npm,
+        Manager::Flatpak,
+    ];
+
+    /// Program & args that list packages installed explicitly.
+    fn list_cmd(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Manager::Apt => ("apt-mark", &["showmanual"]),
+            Manager::Dnf => ("dnf", &["repoquery", "--userinstalled", "--qf", "%{name}"]),
+            Manager::Pacman => ("pacman", &["-Qqe"]),
+            Manager::Brew => ("brew", &["leaves", "--installed-on-request"]),
+            Manager::Cargo => ("cargo", &["install", "--list"]),
+            Manager::Pip => ("pip", &["list", "--not-required", "--format=freeze"]),
+            Manager::Npm => ("npm", &["ls", "--global", "--depth=0", "--json"]),
+            Manager::Flatpak => ("flatpak", &["list", "--app", "--columns=application"]),
+        }
+    }
+
+    /// Program & args that install packages, which are appended.
+    fn install_cmd(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Manager::Apt => ("apt-get", &["install", "--yes"]),
+            Manager::Dnf => ("dnf", &["install", "--assumeyes"]),
+            Manager::Pacman => ("pacman", &["-S", "--needed", "--noconfirm"]),
+            Manager::Brew => ("brew", &["install"]),
+            Manager::Cargo => ("cargo", &["install"]),
+            Manager::Pip => ("pip", &["install", "--user"]),
+            Manager::Npm => ("npm", &["install", "--global"]),
+            Manager::Flatpak => ("flatpak", &["install", "--noninteractive"]),
+        }
+    }
+
+    /// Whether this manager installs for every user, which needs root.
+    pub fn is_system(&self) -> bool {
+        matches!(self, Manager::Apt | Manager::Dnf | Manager::Pacman)
+    }
+
+    /// Whether this manager is installed, i.e. its programs are on `path`.
+    ///
+    /// * `path`: &OsStr, $PATH to find programs on.
+    pub fn is_available(&self, path: &OsStr) -> bool {
+        [self.list_cmd().0, self.install_cmd().0]
+            .iter()
+            .all(|prog| utils::is_on(prog, path))
+    }
+
+    /// Packages installed explicitly, sorted.
+    ///
+    /// * `path`: &OsStr, $PATH to find programs on.
+    pub fn installed(&self, path: &OsStr) -> anyhow::Result<Vec<String>> {
+        let (prog, args) = self.list_cmd();
+        let output = utils::run_cmd_env(prog, args, [("PATH", path)])?;
+
+        let mut packages: Vec<String> = match self {
+            // `name v1.0.0:`, followed by its binaries, indented.
+            Manager::Cargo => output
+                .lines()
+                .filter(|line| !line.starts_with(char::is_whitespace))
+                .filter_map(|line| line.split_whitespace().next())
+                .map(str::to_string)
+                .collect(),
+            // `name==1.0.0`
+            Manager::Pip => output
+                .lines()
+                .filter_map(|line| line.split("==").next())
+                .map(str::to_string)
+                .collect(),
+            Manager::Npm => {
+                let json: serde_json::Value = serde_json::from_str(&output)?;
+                json.get("dependencies")
+                    .and_then(serde_json::Value::as_object)
+                    .map(|deps| deps.keys().cloned().collect())
+                    .unwrap_or_default()
+            }
+            _ => output
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        };
+
+        packages.sort();
+        packages.dedup();
+        Ok(packages)
+    }
+
+    /// Those of `wanted` that aren't installed.
+    ///
+    /// * `wanted`: &[String]
+    /// * `path`: &OsStr, $PATH to find programs on.
+    pub fn missing(&self, wanted: &[String], path: &OsStr) -> anyhow::Result<Vec<String>> {
+        let installed = self.installed(path)?;
+        Ok(wanted
+            .iter()
+            .filter(|package| !installed.contains(package))
+            .cloned()
+            .collect())
+    }
+
+    /// Install `packages`, with `sudo` if this is a system manager & we
+    /// aren't root.
+    ///
+    /// * `packages`: &[String]
+    /// * `path`: &OsStr, $PATH to find programs on.
+    pub fn install(&self, packages: &[String], path: &OsStr) -> anyhow::Result<()> {
+        let sudo = self.is_system() && users::get_effective_uid() != 0;
+        if sudo && !utils::is_on("sudo", path) {
+            anyhow::bail!("{} needs root, run as root or install `sudo`", self);
+        }
+
+        let args = self.install_args(packages, sudo);
+        utils::run_cmd_env(args[0], &args[1..], [("PATH", path)])?;
+        Ok(())
+    }
+
+    /// Program & args that install `packages`.
+    ///
+    /// * `packages`: &'a [String]
+    /// * `sudo`: bool, whether to run as root with `sudo`.
+    fn install_args<'a>(&self, packages: &'a [String], sudo: bool) -> Vec<&'a str> {
+        let (prog, args) = self.install_cmd();
+        let mut cmd = match sudo {
+            true => vec!["sudo", prog],
+            false => vec![prog],
+        };
+        cmd.extend(args);
+        cmd.extend(packages.iter().map(String::as_str));
+        cmd
+    }
+}
+
+impl std::fmt::Display for Manager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = serde_yaml::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", name.as_str().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Manager;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn missing_packages_are_installed_with_stubs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let log = dir.join("log");
+
+        let stub = |name: &str, body: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        };
+        stub(
+            "pacman",
+            &format!(
+                "case \"$1\" in -Qqe) printf 'git\\nvim\\n' ;; *) echo \"$@\" >> {} ;; esac",
+                log.display()
+            ),
+        );
+        stub(
+            "cargo",
+            "printf 'ripgrep v14.1.0:\\n    rg\\nbat v0.24.0:\\n    bat\\n'",
+        );
+
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let mut paths = vec![dir.to_path_buf()];
+        paths.extend(std::env::split_paths(&path));
+        let path = std::env::join_paths(paths).unwrap();
+
+        assert!(Manager::Pacman.is_available(&path));
+        assert_eq!(Manager::Cargo.installed(&path).unwrap(), ["bat", "ripgrep"]);
+
+        let wanted = ["git", "zsh"].map(String::from);
+        let missing = Manager::Pacman.missing(&wanted, &path).unwrap();
+        assert_eq!(missing, ["zsh"]);
+        assert_eq!(
+            Manager::Pacman.install_args(&missing, true),
+            ["sudo", "pacman", "-S", "--needed", "--noconfirm", "zsh"]
+        );
+        assert_eq!(Manager::Cargo.install_args(&missing, false)[0], "cargo");
+
+        if users::get_effective_uid() == 0 {
+            Manager::Pacman.install(&missing, &path).unwrap();
+            assert_eq!(
+                std::fs::read_to_string(&log).unwrap(),
+                "-S --needed --noconfirm zsh\n"
+            );
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! What a command did, for scripts. With `--output json`, the `Report` of a
+//! command is printed to stdout as a single line of JSON, e.g.
+//!
+//! ```json
+//! {"ok":true,"actions":[{"action":"copied","from":"…","to":"…"}]}
+//! ```
+//!
+//! while logs, which always go to stderr, are left for humans. A command that
+//! fails part way reports what it did up to then, along with `error`.
+
+use super::packages::Manager;
+use crate::errors::Error;
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+static OUTPUT: OnceLock<Output> = OnceLock::new();
+
+/// How a command's results are printed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Output {
+    /// Logs & tables, for humans
+    #[default]
+    Human,
+    /// A `Report` as JSON on stdout, logs on stderr
+    Json,
+}
+
+impl Output {
+    /// Set for the rest of the process, by `RootCmd` before any command runs.
+    pub fn set(self) {
+        if OUTPUT.set(self).is_err() {
+            log::debug!("Output is already set");
+        }
+    }
+
+    pub fn current() -> Output {
+        OUTPUT.get().copied().unwrap_or_default()
+    }
+
+    pub fn is_json() -> bool {
+        Output::current() == Output::Json
+    }
+}
+
+/// Something a command did, or failed to do.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// A file or directory was copied, e.g. by `install` or `gather`.
+    Copied { from: PathBuf, to: PathBuf },
+    /// Nothing needed doing, e.g. `to` was already identical to `from`.
+    Skipped { from: PathBuf, to: PathBuf },
+    /// `path` is a source, git repository, or anything else that failed.
+    Failed { path: PathBuf, error: String },
+    /// Added to `nedots.yml`.
+    Added { path: PathBuf },
+    /// Removed from `nedots.yml`, & from `dots_dir` if `deleted`.
+    Removed { path: PathBuf, deleted: bool },
+    /// Deleted from `dots_dir`, as it was from its source, by `watch`.
+    Deleted { path: PathBuf },
+    /// Moved within `dots_dir`, by `migrate`.
+    Moved { from: PathBuf, to: PathBuf },
+    /// A git repository was cloned, or checked out at another commit.
+    Cloned { remote: String, path: PathBuf },
+    /// A git repository was already up to date with `remote`.
+    UpToDate { remote: String, path: PathBuf },
+    /// A git repository was synced with its remote.
+    Synced {
+        path: PathBuf,
+        status: String,
+        result: String,
+    },
+    /// Everything was committed, e.g. by `watch`.
+    Committed { path: PathBuf },
+    /// `sources` were backed up to `path`.
+    BackedUp { path: PathBuf },
+    /// `path` was emptied, by `clean`.
+    Cleaned { path: PathBuf },
+    /// A file was written, e.g. a sample config or systemd unit.
+    Written { path: PathBuf },
+    /// Packages were installed, or would be if `dry_run`.
+    Installed {
+        manager: Manager,
+        packages: Vec<String>,
+        dry_run: bool,
+    },
+}
+
+impl Action {
+    /// `Copied` if `changed`, otherwise `Skipped`, see `ops::copy`.
+    ///
+    /// * `from`: &Path
+    /// * `to`: &Path
+    /// * `changed`: bool
+    pub fn copy(from: &std::path::Path, to: &std::path::Path, changed: bool) -> Action {
+        let (from, to) = (from.to_path_buf(), to.to_path_buf());
+        match changed {
+            true => Action::Copied { from, to },
+            false => Action::Skipped { from, to },
+        }
+    }
+
+    /// * `path`: impl Into<PathBuf>
+    /// * `err`: &anyhow::Error
+    pub fn failed(path: impl Into<PathBuf>, err: &anyhow::Error) -> Action {
+        Action::Failed {
+            path: path.into(),
+            error: format!("{:#}", err),
+        }
+    }
+
+    /// Whether anything changed, i.e. it wasn't skipped or a failure.
+    pub fn is_changed(&self) -> bool {
+        !matches!(
+            self,
+            Action::Skipped { .. } | Action::UpToDate { .. } | Action::Failed { .. }
+        )
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct Report {
+    pub actions: Vec<Action>,
+    /// Anything else a command prints, e.g. the entries of `list`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Report {
+    /// A `Report` of `data` alone.
+    ///
+    /// * `data`: &impl serde::Serialize
+    pub fn with_data(data: &impl serde::Serialize) -> anyhow::Result<Report> {
+        Ok(Report {
+            actions: Vec::new(),
+            data: Some(serde_json::to_value(data)?),
+        })
+    }
+
+    pub fn push(&mut self, action: Action) {
+        self.actions.push(action);
+    }
+
+    /// Whether any action changed anything.
+    pub fn is_changed(&self) -> bool {
+        self.actions.iter().any(Action::is_changed)
+    }
+
+    /// Push the `Action` of `result`, or `Failed` @ `path` so that the rest
+    /// may be attempted, see `check`. Unless `fail_fast`, in which case the
+    /// failure is returned.
+    ///
+    /// * `path`: &Path, that was attempted.
+    /// * `result`: anyhow::Result<Action>
+    /// * `fail_fast`: bool
+    pub fn record(
+        &mut self,
+        path: &Path,
+        result: anyhow::Result<Action>,
+        fail_fast: bool,
+    ) -> anyhow::Result<()> {
+        match result {
+            Ok(action) => self.push(action),
+            Err(err) if fail_fast => return Err(err),
+            Err(err) => self.push(Action::failed(path, &err)),
+        }
+        Ok(())
+    }
+
+    /// Log what failed & why, then fail with `Error::Incomplete`, if anything
+    /// failed.
+    ///
+    /// * `what`: &str, that was attempted, e.g. `sources`.
+    pub fn check(&mut self, what: &str) -> anyhow::Result<()> {
+        let failed: Vec<(&PathBuf, &String)> = self
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::Failed { path, error } => Some((path, error)),
+                _ => None,
+            })
+            .collect();
+
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        for (path, error) in &failed {
+            log::error!("❌ {} ({})", console::style(path.display()).blue(), error);
+        }
+
+        let msg = format!("{} of {} {} failed", failed.len(), self.actions.len(), what);
+        Err(std::mem::take(self).incomplete(msg))
+    }
+
+    /// Fail with `msg`, keeping this `Report`, see `Error::Incomplete`.
+    ///
+    /// * `msg`: String
+    pub fn incomplete(self, msg: String) -> anyhow::Error {
+        Error::Incomplete { msg, report: self }.into()
+    }
+
+    /// Fail with `msg`, keeping this `Report`, see `Error::Invalid`.
+    ///
+    /// * `msg`: String
+    pub fn invalid(self, msg: String) -> anyhow::Error {
+        Error::Invalid { msg, report: self }.into()
+    }
+}
+
+/// Print `result` as a single line of JSON on stdout.
+///
+/// * `result`: &anyhow::Result<Report>
+pub fn print_json(result: &anyhow::Result<Report>) {
+    #[derive(serde::Serialize)]
+    struct Json<'a> {
+        ok: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        #[serde(flatten, skip_serializing_if = "Option::is_none")]
+        report: Option<&'a Report>,
+    }
+
+    let json = match result {
+        Ok(report) => Json {
+            ok: true,
+            error: None,
+            report: Some(report),
+        },
+        Err(err) => Json {
+            ok: false,
+            error: Some(format!("{:#}", err)),
+            report: match err.downcast_ref::<Error>() {
+                Some(Error::Incomplete { report, .. } | Error::Invalid { report, .. }) => {
+                    Some(report)
+                }
+                _ => None,
+            },
+        },
+    };
+
+    match serde_json::to_string(&json) {
+        Ok(json) => println!("{}", json),
+        Err(err) => log::error!("❌ Failed to print JSON ({})", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Report};
+    use crate::errors::Error;
+    use std::path::Path;
+
+    #[test]
+    fn actions_are_tagged() {
+        let mut report = Report::default();
+        report.push(Action::copy(Path::new("/a"), Path::new("/b"), false));
+        assert!(!report.is_changed());
+        report.push(Action::BackedUp {
+            path: "/backups/1".into(),
+        });
+        assert!(report.is_changed());
+
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"actions":[{"action":"skipped","from":"/a","to":"/b"},{"action":"backed_up","path":"/backups/1"}]}"#
+        );
+
+        let err = report.incomplete("1 of 2 failed".to_string());
+        assert_eq!(err.to_string(), "1 of 2 failed");
+    }
+
+    #[test]
+    fn failures_are_collected_unless_failing_fast() {
+        let mut report = Report::default();
+        let ok = || Ok(Action::copy(Path::new("/a"), Path::new("/b"), true));
+        let err = || Err(anyhow::anyhow!("denied"));
+
+        report.record(Path::new("/a"), ok(), false).unwrap();
+        assert!(report.check("sources").is_ok());
+        report.record(Path::new("/c"), err(), false).unwrap();
+        assert!(report.record(Path::new("/d"), err(), true).is_err());
+
+        let err = report.check("sources").unwrap_err();
+        assert_eq!(err.to_string(), "1 of 2 sources failed");
+        match err.downcast::<Error>().unwrap() {
+            Error::Incomplete { report, .. } => assert_eq!(
+                report.actions[1],
+                Action::Failed {
+                    path: "/c".into(),
+                    error: "denied".to_string()
+                }
+            ),
+            err => panic!("{:?}", err),
+        }
+    }
+}
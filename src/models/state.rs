@@ -0,0 +1,61 @@
+use crate::utils::paths::MakeDirs;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// State that `nedots` keeps between runs. Lives in the `.git` directory of the
+/// root repository, so that it's never committed.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+pub struct State {
+    /// Repositories with commits that couldn't be pushed, because their remote
+    /// was unreachable.
+    #[serde(default)]
+    pub pending_push: Vec<PathBuf>,
+}
+
+impl State {
+    /// Path of the state file for the root repository @ `root`.
+    ///
+    /// * `root`: &Path
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(".git").join("nedots-state.yml")
+    }
+
+    /// Read `State` from `path`. A missing file is an empty `State`.
+    ///
+    /// * `path`: &Path
+    pub fn read(path: &Path) -> anyhow::Result<State> {
+        if !path.exists() {
+            return Ok(State::default());
+        }
+
+        log::trace!("Reading `{}`...", path.display());
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read `{}`", path.display()))?;
+
+        serde_yaml::from_str(&raw)
+            .with_context(|| format!("Failed to deserialize `{}`", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        log::trace!("Writing `{}`...", path.display());
+        if let Some(parent) = path.parent() {
+            parent.make_all_dirs()?;
+        }
+
+        std::fs::write(path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Record that `repo` has commits waiting to be pushed.
+    ///
+    /// * `repo`: &Path
+    pub fn add_pending_push(&mut self, repo: &Path) {
+        if !self.pending_push.iter().any(|p| p == repo) {
+            self.pending_push.push(repo.to_path_buf());
+        }
+    }
+
+    pub fn remove_pending_push(&mut self, repo: &Path) {
+        self.pending_push.retain(|p| p != repo);
+    }
+}
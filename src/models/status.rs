@@ -0,0 +1,48 @@
+//! The state of everything `nedots` manages, see `Nedots::status`.
+
+use super::git_repo::RepoStatus;
+use std::path::PathBuf;
+
+/// How a source compares to its copy in `dots_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceState {
+    /// Neither the source, nor its copy, exists.
+    Missing,
+    /// Only the source exists, it's yet to be gathered.
+    Ungathered,
+    /// Only the copy exists, it's yet to be installed.
+    Uninstalled,
+    /// Both exist & are identical, ignoring `ignore`.
+    Unchanged,
+    /// Both exist & differ.
+    Modified,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SourceStatus {
+    /// As it appears in `nedots.yml`.
+    pub source: PathBuf,
+    /// Absolute path of `source`.
+    pub path: PathBuf,
+    /// Copy in `dots_dir`.
+    pub dots: PathBuf,
+    pub state: SourceState,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GitRepoStatus {
+    pub remote: String,
+    /// Absolute.
+    pub path: PathBuf,
+    /// `None` if it hasn't been cloned.
+    pub status: Option<RepoStatus>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Status {
+    /// Of the root repository, `None` if it hasn't been cloned.
+    pub root: Option<RepoStatus>,
+    pub sources: Vec<SourceStatus>,
+    pub git_repos: Vec<GitRepoStatus>,
+}
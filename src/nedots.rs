@@ -0,0 +1,235 @@
+//! `Nedots`, to manage dots from other programs, without the CLI.
+
+use crate::{
+    errors::{Error, Result},
+    models::{
+        config::{self, Config},
+        git_repo::{GitRepo, RepoStatus},
+        hooks::Event,
+        report::{Action, Report},
+        status::{GitRepoStatus, SourceState, SourceStatus, Status},
+    },
+    ops,
+    utils::{ignore::Ignore, paths::expand_path},
+};
+use std::path::{Path, PathBuf};
+
+/// Everything `nedots` can do with a `Config`. Each method runs the same hooks
+/// as the command of the same name, & returns a `Report` of what was done.
+/// Every source is attempted, even if some fail, unless `fail_fast`; what was
+/// done is then kept by `Error::Incomplete`.
+///
+/// ```no_run
+/// use nedots::Nedots;
+///
+/// let nedots = Nedots::open("/home/me/.config/nedots/nedots.yml".as_ref())?;
+/// nedots.backup()?;
+/// for action in nedots.install()?.actions {
+///     println!("{:?}", action);
+/// }
+/// # Ok::<(), nedots::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Nedots {
+    config: Config,
+    fail_fast: bool,
+}
+
+impl Nedots {
+    /// `root`, `dots_dir` & `backup_dir` are decided as they are for the CLI,
+    /// see `Config::set_dirs`. `sources` needn't exist.
+    ///
+    /// * `config`: Config
+    pub fn new(mut config: Config) -> Result<Nedots> {
+        config.set_dirs(None, None, None)?;
+        Ok(Nedots {
+            config: config.resolve_dirs(),
+            fail_fast: false,
+        })
+    }
+
+    /// `config` as the CLI has already prepared it, see `Initialize`.
+    ///
+    /// * `config`: Config
+    /// * `fail_fast`: bool
+    pub(crate) fn prepared(config: Config, fail_fast: bool) -> Nedots {
+        Nedots { config, fail_fast }
+    }
+
+    /// Read `nedots.yml`, or `nedots.toml` etc., along with anything it
+    /// includes.
+    ///
+    /// * `path`: &Path
+    pub fn open(path: &Path) -> Result<Nedots> {
+        Nedots::new(config::read(path)?)
+    }
+
+    /// Stop at the first source or repository that fails, rather than
+    /// attempting the rest.
+    ///
+    /// * `fail_fast`: bool
+    pub fn fail_fast(mut self, fail_fast: bool) -> Nedots {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Copy each of `sources` into `dots_dir`, skipping anything in `ignore`.
+    pub fn gather(&self) -> Result<Report> {
+        let mut report = Report::default();
+        self.config
+            .hooks
+            .around(Event::Gather, &self.config.hook_env(), || {
+                ops::gather(&self.config, &mut report, self.fail_fast)
+            })?;
+
+        report.check("sources")?;
+        Ok(report)
+    }
+
+    /// Copy each of `sources` from `dots_dir`, & clone each of `git_repos`.
+    pub fn install(&self) -> Result<Report> {
+        let sources: Vec<&Path> = self.config.sources.iter().map(PathBuf::as_path).collect();
+        self.install_with(&sources, &self.config.git_repos)
+    }
+
+    /// Copy only `sources` from `dots_dir`, each as it's listed in `sources`.
+    /// No repositories are cloned.
+    ///
+    /// * `sources`: &[&Path]
+    pub fn install_sources(&self, sources: &[&Path]) -> Result<Report> {
+        self.install_with(sources, &[])
+    }
+
+    /// * `sources`: &[&Path]
+    /// * `git_repos`: &[GitRepo]
+    fn install_with(&self, sources: &[&Path], git_repos: &[GitRepo]) -> Result<Report> {
+        let mut report = Report::default();
+        self.config
+            .hooks
+            .around(Event::Install, &self.config.hook_env(), || {
+                for source in sources {
+                    let result = ops::install_source(&self.config, source);
+                    report.record(source, result, self.fail_fast)?;
+                }
+
+                for repo in git_repos {
+                    let result = ops::install_repo(&self.config, repo);
+                    report.record(&repo.path, result, self.fail_fast)?;
+                }
+
+                Ok(report.is_changed())
+            })?;
+
+        report.check("sources & repositories")?;
+        Ok(report)
+    }
+
+    /// Copy each of `sources` to `backup_dir/{timestamp}`.
+    pub fn backup(&self) -> Result<Report> {
+        let dst = self.config.backup_dir.join(crate::utils::get_timestamp());
+        let mut report = Report::default();
+        let changed = self
+            .config
+            .hooks
+            .around(Event::Backup, &self.config.hook_env(), || {
+                ops::backup(&self.config, &dst, &mut report, self.fail_fast)
+            })?;
+        report.check("sources")?;
+
+        if changed {
+            report.push(Action::BackedUp { path: dst });
+        }
+        Ok(report)
+    }
+
+    /// Copy each of `sources` back from `backup`, or the latest backup. The
+    /// backup hooks are run, as they are by `backup`.
+    ///
+    /// * `backup`: Option<&Path>, one of `backups`.
+    pub fn restore(&self, backup: Option<&Path>) -> Result<Report> {
+        let backup = match backup {
+            Some(backup) => backup.to_path_buf(),
+            None => ops::backups(&self.config)?
+                .pop()
+                .ok_or_else(|| Error::NotFound("A backup".to_string()))?,
+        };
+
+        let mut report = Report::default();
+        self.config
+            .hooks
+            .around(Event::Backup, &self.config.hook_env(), || {
+                ops::restore(&self.config, &backup, &mut report, self.fail_fast)
+            })?;
+        report.check("sources")?;
+        Ok(report)
+    }
+
+    /// Each backup in `backup_dir`, oldest first.
+    pub fn backups(&self) -> Result<Vec<PathBuf>> {
+        Ok(ops::backups(&self.config)?)
+    }
+
+    /// Compare each of `sources` with its copy in `dots_dir`, & check the
+    /// status of each repository. Nothing is fetched, so `ahead` & `behind` are
+    /// as of the last `sync`.
+    pub fn status(&self) -> Result<Status> {
+        let ignore = Ignore::new(&self.config.ignore)?;
+        let mut sources = Vec::new();
+        for source in &self.config.sources {
+            let path = expand_path(source)?;
+            let dots = self.config.dots_path(source)?;
+
+            let state = match (path.exists(), dots.exists()) {
+                (false, false) => SourceState::Missing,
+                (true, false) => SourceState::Ungathered,
+                (false, true) => SourceState::Uninstalled,
+                (true, true)
+                    if ops::differs(&path, &dots, &ignore)?
+                        || ops::differs(&dots, &path, &ignore)? =>
+                {
+                    SourceState::Modified
+                }
+                (true, true) => SourceState::Unchanged,
+            };
+
+            sources.push(SourceStatus {
+                source: source.clone(),
+                path,
+                dots,
+                state,
+            });
+        }
+
+        let mut git_repos = Vec::new();
+        for repo in &self.config.git_repos {
+            let mut repo = Clone::clone(repo);
+            repo.path = expand_path(&repo.path)?;
+
+            git_repos.push(GitRepoStatus {
+                status: repo_status(&repo)?,
+                remote: repo.remote,
+                path: repo.path,
+            });
+        }
+
+        Ok(Status {
+            root: repo_status(&self.config.root_repo())?,
+            sources,
+            git_repos,
+        })
+    }
+}
+
+/// `GitRepo::status`, or `None` if `repo` hasn't been cloned.
+///
+/// * `repo`: &GitRepo
+fn repo_status(repo: &GitRepo) -> anyhow::Result<Option<RepoStatus>> {
+    match repo.path.join(".git").exists() {
+        true => Ok(Some(repo.status()?)),
+        false => Ok(None),
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    models::{
+        config::Config,
+        hooks::Event,
+        report::{Action, Report},
+    },
+    utils::paths::{expand_path, MakeDirs},
+};
+use std::path::{Path, PathBuf};
+
+/// Make directory, `backup_dir/{timestamp}` and loop through `sources`. Copy
+/// each that exists to `dst`, between its hooks. Returns whether anything was
+/// copied.
+///
+/// * `config`: &Config
+/// * `to`: &Path
+/// * `report`: &mut Report, of each source copied, or that failed.
+/// * `fail_fast`: bool, stop at the first source that fails, see
+///   `Report::record`.
+pub fn backup(
+    config: &Config,
+    to: &Path,
+    report: &mut Report,
+    fail_fast: bool,
+) -> anyhow::Result<bool> {
+    log::trace!("Backing up to `{}`", to.display().to_string());
+
+    to.make_all_dirs()?;
+    let mut changed = false;
+    for source in &config.sources {
+        let src = expand_path(source)?;
+        if !src.exists() {
+            log::debug!("`{}` doesn't exist, nothing to back up", src.display());
+            continue;
+        }
+
+        let result = backup_source(config, source, &src, to);
+        changed |= result.as_ref().is_ok_and(Action::is_changed);
+        report.record(source, result, fail_fast)?;
+    }
+
+    Ok(changed)
+}
+
+/// * `config`: &Config
+/// * `source`: &Path, as listed in `sources`.
+/// * `src`: &Path, `source` expanded.
+/// * `to`: &Path, the backup.
+fn backup_source(config: &Config, source: &Path, src: &Path, to: &Path) -> anyhow::Result<Action> {
+    let dst = crate::utils::join_paths(to, src);
+
+    let mut env = config.hook_env();
+    env.push(("NEDOTS_SRC", src.to_path_buf().into()));
+    env.push(("NEDOTS_DST", dst.clone().into()));
+
+    let changed = config
+        .hooks_of(source)
+        .around(Event::Backup, &env, || super::copy(src, &dst))?;
+    Ok(Action::copy(src, &dst, changed))
+}
+
+/// Copy each of `sources` back from `from`, a backup made by `backup`, between
+/// its backup hooks. Sources missing from the backup are left as they are.
+/// Returns whether anything was copied.
+///
+/// * `config`: &Config
+/// * `from`: &Path, e.g. `backup_dir/{timestamp}`.
+/// * `report`: &mut Report, of each source copied, or that failed.
+/// * `fail_fast`: bool, stop at the first source that fails, see
+///   `Report::record`.
+pub fn restore(
+    config: &Config,
+    from: &Path,
+    report: &mut Report,
+    fail_fast: bool,
+) -> anyhow::Result<bool> {
+    log::trace!("Restoring from `{}`", from.display());
+
+    let mut changed = false;
+    for source in &config.sources {
+        let dst = expand_path(source)?;
+        let src = crate::utils::join_paths(from, &dst);
+        if !src.exists() {
+            log::debug!("`{}` isn't backed up", dst.display());
+            continue;
+        }
+
+        let mut env = config.hook_env();
+        env.push(("NEDOTS_SRC", src.clone().into()));
+        env.push(("NEDOTS_DST", dst.clone().into()));
+
+        let result = config
+            .hooks_of(source)
+            .around(Event::Backup, &env, || super::copy(&src, &dst))
+            .map(|copied| Action::copy(&src, &dst, copied));
+        changed |= result.as_ref().is_ok_and(Action::is_changed);
+        report.record(source, result, fail_fast)?;
+    }
+
+    Ok(changed)
+}
+
+/// Each backup in `backup_dir`, oldest first.
+///
+/// * `config`: &Config
+pub fn backups(config: &Config) -> anyhow::Result<Vec<PathBuf>> {
+    if !config.backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in config.backup_dir.read_dir()? {
+        let path = entry?.path();
+        if path.is_dir() {
+            backups.push(path);
+        }
+    }
+
+    // Named by `get_timestamp`, so they sort by age.
+    backups.sort();
+    Ok(backups)
+}
@@ -1,7 +1,60 @@
-use crate::utils::paths::{MakeDirs, Metadata};
+use crate::{
+    errors::Error,
+    utils::{
+        ignore::Ignore,
+        paths::{MakeDirs, Metadata},
+    },
+};
 use std::path::Path;
 
-pub fn copy(from: &Path, to: &Path) -> anyhow::Result<()> {
+/// Copy `from` to `to`, recursively if `from` is a directory. Files that are
+/// already identical are skipped. Files that fail to copy don't stop the rest,
+/// they're returned together once everything else is copied, see
+/// `Error::CopyMany`. Returns whether anything was copied.
+///
+/// * `from`: &Path
+/// * `to`: &Path
+pub fn copy(from: &Path, to: &Path) -> anyhow::Result<bool> {
+    copy_ignoring(from, to, &Ignore::default())
+}
+
+/// `copy`, skipping anything within `from` that's ignored by `ignore`.
+///
+/// * `from`: &Path
+/// * `to`: &Path
+/// * `ignore`: &Ignore, matched against paths relative to `from`.
+pub fn copy_ignoring(from: &Path, to: &Path, ignore: &Ignore) -> anyhow::Result<bool> {
+    let mut errors = Vec::new();
+    let changed = copy_within(from, to, Path::new(""), ignore, &mut errors)?;
+
+    if errors.is_empty() {
+        return Ok(changed);
+    }
+
+    let first = errors.remove(0);
+    match errors.is_empty() {
+        true => Err(first.into()),
+        false => Err(Error::CopyMany {
+            from: from.to_path_buf(),
+            first: Box::new(first),
+            others: errors,
+        }
+        .into()),
+    }
+}
+
+/// * `from`: &Path
+/// * `to`: &Path
+/// * `relative`: &Path, of `from` to where copying began.
+/// * `ignore`: &Ignore
+/// * `errors`: &mut Vec<Error>, of files that failed to copy.
+fn copy_within(
+    from: &Path,
+    to: &Path,
+    relative: &Path,
+    ignore: &Ignore,
+    errors: &mut Vec<Error>,
+) -> anyhow::Result<bool> {
     log::trace!("Copying `{}` -> `{}`", from.display(), to.display());
 
     // There are a couple of ways to check if a given path can be considered a
@@ -13,15 +66,31 @@ pub fn copy(from: &Path, to: &Path) -> anyhow::Result<()> {
         src_is_dir = src_metadata.is_dir();
     }
 
+    let mut changed = false;
     if src_is_dir {
         // When given a directory as `src`, we've been asked to copy the
         // contents of a directory into the `dst` path - we want to create
         // the same directory structure as defined in `src`, so we call `gather`
         // once again, this time with the `src` directory name appended to
         // `dst`.
-        for entry in from.read_dir()? {
+        let entries = match from.read_dir() {
+            Ok(entries) => entries,
+            Err(err) => {
+                errors.push(copy_error(from, to, err));
+                return Ok(false);
+            }
+        };
+
+        for entry in entries {
             let path = entry?.path();
-            copy(&path, &to.join(path.file_name().unwrap()))?;
+            let name = path.file_name().unwrap();
+            let relative = relative.join(name);
+            if ignore.is_ignored(&relative) {
+                log::trace!("Ignoring `{}`", path.display());
+                continue;
+            }
+
+            changed |= copy_within(&path, &to.join(name), &relative, ignore, errors)?;
         }
     } else {
         // Now that we are positive we're not handling any directories, it's
@@ -32,10 +101,91 @@ pub fn copy(from: &Path, to: &Path) -> anyhow::Result<()> {
             }
         }
 
-        if let Err(err) = std::fs::copy(from, to) {
-            log::warn!("Couldn't copy {} ({})", from.display(), err);
+        if is_identical(from, to) {
+            log::trace!("`{}` is unchanged", to.display());
+        } else if let Err(err) = std::fs::copy(from, to) {
+            log::debug!("Couldn't copy `{}` ({})", from.display(), err);
+            errors.push(copy_error(from, to, err));
+        } else {
+            changed = true;
         }
     }
 
-    Ok(())
+    Ok(changed)
+}
+
+/// Whether `copy_ignoring` would copy anything, i.e. whether any file within
+/// `from` is missing from `to`, or differs.
+///
+/// * `from`: &Path
+/// * `to`: &Path
+/// * `ignore`: &Ignore, matched against paths relative to `from`.
+pub fn differs(from: &Path, to: &Path, ignore: &Ignore) -> anyhow::Result<bool> {
+    differs_within(from, to, Path::new(""), ignore)
+}
+
+/// * `from`: &Path
+/// * `to`: &Path
+/// * `relative`: &Path, of `from` to where comparing began.
+/// * `ignore`: &Ignore
+fn differs_within(
+    from: &Path,
+    to: &Path,
+    relative: &Path,
+    ignore: &Ignore,
+) -> anyhow::Result<bool> {
+    if !from.is_dir() {
+        return Ok(!is_identical(from, to));
+    }
+
+    for entry in from.read_dir()? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap();
+        let relative = relative.join(name);
+        if !ignore.is_ignored(&relative)
+            && differs_within(&path, &to.join(name), &relative, ignore)?
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// `Error::PermissionDenied` for whichever of `from` or `to` it was denied,
+/// otherwise `Error::Copy`.
+///
+/// * `from`: &Path
+/// * `to`: &Path
+/// * `err`: std::io::Error, of `std::fs::copy`.
+fn copy_error(from: &Path, to: &Path, err: std::io::Error) -> Error {
+    if err.kind() != std::io::ErrorKind::PermissionDenied {
+        return Error::Copy {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            err,
+        };
+    }
+
+    let path = match std::fs::File::open(from) {
+        Err(_) => from,
+        Ok(_) => to,
+    };
+    Error::PermissionDenied {
+        path: path.to_path_buf(),
+        err,
+    }
+}
+
+/// Whether files `a` & `b` both exist with the same contents.
+///
+/// * `a`: &Path
+/// * `b`: &Path
+fn is_identical(a: &Path, b: &Path) -> bool {
+    match (a.metadata(), b.metadata()) {
+        (Ok(a_meta), Ok(b_meta)) if a_meta.len() == b_meta.len() => {
+            matches!((std::fs::read(a), std::fs::read(b)), (Ok(a), Ok(b)) if a == b)
+        }
+        _ => false,
+    }
 }
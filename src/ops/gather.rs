@@ -0,0 +1,65 @@
+use crate::{
+    models::{
+        config::Config,
+        hooks::Event,
+        report::{Action, Report},
+    },
+    utils::{ignore::Ignore, paths::expand_path},
+};
+use std::path::{Path, PathBuf};
+
+/// Copy each of `sources` into `dots_dir`, between its hooks, skipping
+/// anything in `ignore`. Sources that don't exist are skipped, keeping any
+/// copy of them. Returns whether anything changed.
+///
+/// * `config`: &Config
+/// * `report`: &mut Report, of each source copied, or that failed.
+/// * `fail_fast`: bool, stop at the first source that fails, see
+///   `Report::record`.
+pub fn gather(config: &Config, report: &mut Report, fail_fast: bool) -> anyhow::Result<bool> {
+    gather_sources(config, &config.sources, report, fail_fast)
+}
+
+/// Same as `gather`, but only of `sources`, e.g. those just added.
+///
+/// * `config`: &Config
+/// * `sources`: &[PathBuf], as they're listed, or will be, in `sources`.
+/// * `report`: &mut Report
+/// * `fail_fast`: bool
+pub fn gather_sources(
+    config: &Config,
+    sources: &[PathBuf],
+    report: &mut Report,
+    fail_fast: bool,
+) -> anyhow::Result<bool> {
+    let ignore = Ignore::new(&config.ignore)?;
+    let mut changed = false;
+    for source in sources {
+        let result = gather_source(config, source, &ignore);
+        changed |= result.as_ref().is_ok_and(Action::is_changed);
+        report.record(source, result, fail_fast)?;
+    }
+
+    Ok(changed)
+}
+
+/// * `config`: &Config
+/// * `source`: &Path, as listed in `sources`.
+/// * `ignore`: &Ignore
+fn gather_source(config: &Config, source: &Path, ignore: &Ignore) -> anyhow::Result<Action> {
+    let src = expand_path(source)?;
+    let dst = config.dots_path(source)?;
+    if !src.exists() {
+        log::warn!("⚠️ Skipping `{}`, it doesn't exist", source.display());
+        return Ok(Action::copy(&src, &dst, false));
+    }
+
+    let mut env = config.hook_env();
+    env.push(("NEDOTS_SRC", src.clone().into()));
+    env.push(("NEDOTS_DST", dst.clone().into()));
+
+    let changed = config.hooks_of(source).around(Event::Gather, &env, || {
+        super::copy_ignoring(&src, &dst, ignore)
+    })?;
+    Ok(Action::copy(&src, &dst, changed))
+}
@@ -0,0 +1,165 @@
+use crate::{
+    models::{config::Config, git_repo::GitRepo, hooks::Event, report::Action},
+    utils::paths::expand_path,
+};
+use std::path::Path;
+
+const SUCCESS_MSG: &str = "👍 Installed";
+
+/// Copy `source` from `dots_dir`, between its hooks. Sources that haven't been
+/// gathered are skipped. Returns whether anything was copied, as an `Action`.
+///
+/// * `config`: &Config
+/// * `source`: &Path, as listed in `sources`.
+pub fn install_source(config: &Config, source: &Path) -> anyhow::Result<Action> {
+    let src = config.dots_path(source)?;
+    let dst = expand_path(source)?;
+    if !src.exists() {
+        log::warn!(
+            "⚠️ Skipping `{}`, it hasn't been gathered",
+            source.display()
+        );
+        return Ok(Action::copy(&src, &dst, false));
+    }
+
+    let mut env = config.hook_env();
+    env.push(("NEDOTS_SRC", src.clone().into()));
+    env.push(("NEDOTS_DST", dst.clone().into()));
+
+    let changed = config
+        .hooks_of(source)
+        .around(Event::Install, &env, || super::copy(&src, &dst))?;
+
+    log::info!(
+        "{} `{}`",
+        SUCCESS_MSG,
+        console::style(dst.display()).green().bold(),
+    );
+    Ok(Action::copy(&src, &dst, changed))
+}
+
+/// Clone `repo` unless it exists, or check out its submodule's commit, between
+/// its hooks.
+/// Returns whether `HEAD` changed, as an `Action`.
+///
+/// * `config`: &Config
+/// * `repo`: &GitRepo, as listed in `git_repos`.
+pub fn install_repo(config: &Config, repo: &GitRepo) -> anyhow::Result<Action> {
+    let mut repo = Clone::clone(repo);
+    repo.path = expand_path(&repo.path)?;
+
+    let mut env = config.hook_env();
+    env.push(("NEDOTS_SRC", repo.remote.clone().into()));
+    env.push(("NEDOTS_DST", repo.path.clone().into()));
+
+    // `git` complains loudly about paths that aren't repositories.
+    let head = |repo: &GitRepo| match repo.path.join(".git").exists() {
+        true => repo.head().ok(),
+        false => None,
+    };
+
+    let changed = repo.hooks.around(Event::Install, &env, || {
+        let before = head(&repo);
+        if config.submodules {
+            install_submodule(config, &repo)?;
+        } else if !repo.path.exists() {
+            repo.clone()?;
+        } else {
+            log::debug!("`{}` exists", repo.path.display());
+        }
+        Ok(head(&repo) != before)
+    })?;
+
+    log::info!(
+        "{} `{}`",
+        SUCCESS_MSG,
+        console::style(repo.path.display()).green().bold(),
+    );
+    Ok(match changed {
+        true => Action::Cloned {
+            remote: repo.remote,
+            path: repo.path,
+        },
+        false => Action::UpToDate {
+            remote: repo.remote,
+            path: repo.path,
+        },
+    })
+}
+
+/// Clone `repo` if it doesn't yet exist, then check out exactly the commit
+/// recorded by its submodule in the root repository. The submodule itself
+/// needn't be checked out in the root repository.
+///
+/// * `config`: &Config
+/// * `repo`: &GitRepo, `path` is expected to be absolute.
+fn install_submodule(config: &Config, repo: &GitRepo) -> anyhow::Result<()> {
+    let root = config.root_repo();
+    let commit = root.submodule_commit(&config.submodule_path(repo))?;
+
+    if !repo.path.exists() {
+        repo.clone()?;
+    } else {
+        repo.fetch()?;
+    }
+
+    log::trace!("Checking out `{}` @ {}", repo.path.display(), commit);
+    repo.checkout(&commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::install_repo;
+    use crate::{
+        models::{config::Config, git_repo::GitRepo, report::Action},
+        utils::testing::{add_submodule, bare_repo, commit_files, git, head, init_repo},
+    };
+
+    #[test]
+    fn submodules_are_installed_at_their_commit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let remote = bare_repo(dir, "app", &[("app.conf", "v1")]);
+        let recorded = head(&remote);
+
+        let config = Config {
+            root: dir.join("root"),
+            dots_dir: dir.join("root/dots"),
+            submodules: true,
+            git_repos: vec![GitRepo::new(
+                &remote.display().to_string(),
+                &dir.join("home/app"),
+            )],
+            ..Default::default()
+        };
+        let repo = &config.git_repos[0];
+        init_repo(&config.root);
+        add_submodule(&config.root, &remote, &config.submodule_path(repo));
+        // As it is after cloning the root repository.
+        git(&config.root, &["submodule", "--quiet", "deinit", "--all"]);
+
+        // A newer commit on remote isn't installed, the recorded one is.
+        commit_files(&dir.join("app-work"), &[("app.conf", "v2")]);
+        let bare = remote.display().to_string();
+        git(&dir.join("app-work"), &["push", "--quiet", &bare, "main"]);
+
+        let action = install_repo(&config, repo).unwrap();
+        assert!(matches!(action, Action::Cloned { .. }));
+        assert_eq!(head(&repo.path), recorded);
+        // The submodule isn't cloned a second time into the root repository.
+        let submodule = config.root.join(config.submodule_path(repo));
+        assert!(!submodule.join("app.conf").exists());
+
+        // Local commits aren't thrown away, `HEAD` is detached instead.
+        git(&repo.path, &["config", "user.name", "nedots"]);
+        git(&repo.path, &["config", "user.email", "nedots@localhost"]);
+        git(&repo.path, &["checkout", "--quiet", "main"]);
+        commit_files(&repo.path, &[("local.conf", "unpushed")]);
+        let local = head(&repo.path);
+
+        install_repo(&config, repo).unwrap();
+        assert_eq!(head(&repo.path), recorded);
+        let branch = repo.path.join(".git/refs/heads/main");
+        assert_eq!(std::fs::read_to_string(branch).unwrap().trim(), local);
+    }
+}
@@ -1,2 +1,8 @@
+pub mod backup;
 pub mod copy;
-pub use copy::copy;
+pub mod gather;
+pub mod install;
+pub use backup::{backup, backups, restore};
+pub use copy::{copy, copy_ignoring, differs};
+pub use gather::{gather, gather_sources};
+pub use install::{install_repo, install_source};
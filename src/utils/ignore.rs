@@ -0,0 +1,61 @@
+//! `ignore` patterns of `nedots.yml`, files within `sources` that aren't
+//! gathered, e.g. editor swap files or caches.
+//!
+//! A pattern is a glob, matched against the name of each file & directory
+//! within a source, or its path relative to the source, e.g. `*.swp`,
+//! `node_modules` or `cache/**`.
+
+use crate::errors::Error;
+use std::path::Path;
+
+#[derive(Debug, Default)]
+pub struct Ignore {
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Ignore {
+    /// * `patterns`: &[String]
+    pub fn new(patterns: &[String]) -> Result<Self, Error> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                glob::Pattern::new(p).map_err(|err| Error::Ignore {
+                    pattern: p.clone(),
+                    err,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Whether `path`, relative to its source, or any directory it's in, is
+    /// ignored.
+    ///
+    /// * `path`: &Path
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.patterns.iter().any(|pattern| {
+            pattern.matches_path(path)
+                || path
+                    .components()
+                    .any(|c| pattern.matches(&c.as_os_str().to_string_lossy()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ignore;
+    use std::path::Path;
+
+    #[test]
+    fn names_and_relative_paths_are_matched() {
+        let ignore = Ignore::new(&["*.swp", "node_modules", "cache/**"].map(String::from)).unwrap();
+
+        assert!(ignore.is_ignored(Path::new(".init.lua.swp")));
+        assert!(ignore.is_ignored(Path::new("lua/node_modules/x.js")));
+        assert!(ignore.is_ignored(Path::new("cache/a/b")));
+        assert!(!ignore.is_ignored(Path::new("lua/init.lua")));
+        let err = Ignore::new(&["[".to_string()]).unwrap_err();
+        assert_eq!(err.exit_code(), crate::errors::exit::CONFIG);
+    }
+}
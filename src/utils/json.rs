@@ -0,0 +1,139 @@
+//! `serde_json` doesn't keep where values were, so JSON is scanned as text to
+//! locate them, see `locate`.
+
+/// Byte offset of the value @ `path` in `raw`, or of its key if the last of
+/// `path` is a key of an object.
+///
+/// * `raw`: &str
+/// * `path`: &[&str], keys of objects or indices of arrays, e.g.
+///   `["git_repos", "0", "path"]`.
+pub fn locate(raw: &str, path: &[&str]) -> Option<usize> {
+    let mut scanner = Scanner { raw, pos: 0 };
+    let mut offset = None;
+    for key in path {
+        scanner.skip_whitespace();
+        offset = Some(match scanner.next()? {
+            b'{' => scanner.find_key(key)?,
+            b'[' => scanner.find_index(key.parse().ok()?)?,
+            _ => return None,
+        });
+    }
+
+    offset
+}
+
+struct Scanner<'a> {
+    raw: &'a str,
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.raw.as_bytes().get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip past the next `,`, returns `None` @ the end of an object or
+    /// array.
+    fn skip_comma(&mut self) -> Option<()> {
+        self.skip_whitespace();
+        (self.next()? == b',').then_some(())
+    }
+
+    /// Within an object, move to the value of `key`, returning the offset of
+    /// `key`.
+    ///
+    /// * `key`: &str
+    fn find_key(&mut self, key: &str) -> Option<usize> {
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let name: String = serde_json::from_str(self.string()?).ok()?;
+            self.skip_whitespace();
+            if self.next()? != b':' {
+                return None;
+            }
+
+            self.skip_whitespace();
+            if name == key {
+                return Some(start);
+            }
+            self.skip_value()?;
+            self.skip_comma()?;
+        }
+    }
+
+    /// Within an array, move to the value @ `index`, returning its offset.
+    ///
+    /// * `index`: usize
+    fn find_index(&mut self, index: usize) -> Option<usize> {
+        for _ in 0..index {
+            self.skip_whitespace();
+            self.skip_value()?;
+            self.skip_comma()?;
+        }
+
+        self.skip_whitespace();
+        (self.peek()? != b']').then_some(self.pos)
+    }
+
+    /// The next string, quotes included.
+    fn string(&mut self) -> Option<&str> {
+        let start = self.pos;
+        if self.next()? != b'"' {
+            return None;
+        }
+
+        loop {
+            match self.next()? {
+                b'\\' => self.pos += 1,
+                b'"' => return self.raw.get(start..self.pos),
+                _ => (),
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'"' => {
+                self.string()?;
+            }
+            b'{' | b'[' => {
+                let mut depth = 0;
+                loop {
+                    match self.peek()? {
+                        b'"' => {
+                            self.string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => depth -= 1,
+                        _ => (),
+                    }
+                    self.pos += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            _ => {
+                while !matches!(self.peek()?, b',' | b'}' | b']') {
+                    self.pos += 1;
+                }
+            }
+        }
+
+        Some(())
+    }
+}
@@ -0,0 +1,296 @@
+//! Match keys given on the command line, e.g. `install KEY`, against paths in
+//! `sources` or `git_repos`.
+//!
+//! A key is tried against each kind of match in turn, stopping at the first
+//! kind that finds anything:
+//!
+//! 1. Exact path, as written in `nedots.yml` or relative to $HOME.
+//! 2. Path suffix, e.g. `bspwm` or `.config/bspwm` for `.config/bspwm`.
+//! 3. Path components, e.g. `.config` for `.config/bspwm`.
+//! 4. Glob, e.g. `.config/*`. Every match is selected.
+//! 5. Fuzzy, the characters of key appear in order, e.g. `bsp` for
+//!    `.config/bspwm`. The tightest match is picked if it clearly beats the
+//!    rest, e.g. `sw` for `.config/sway/config` over `.config/bspwm`.
+//!
+//! Other than globs, a key that matches more than one path is ambiguous.
+
+use crate::{errors::Error, utils::paths::ResolvePath};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Match<'a> {
+    /// Every path that was selected by key.
+    Found(Vec<&'a Path>),
+    /// Key could mean any of these paths.
+    Ambiguous(Vec<&'a Path>),
+    NotFound,
+}
+
+pub struct Matcher<'a> {
+    paths: Vec<&'a Path>,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(paths: impl IntoIterator<Item = &'a Path>) -> Self {
+        Self {
+            paths: paths.into_iter().collect(),
+        }
+    }
+
+    /// Find paths matching `key`.
+    ///
+    /// * `key`: &str
+    pub fn find(&self, key: &str) -> Match<'a> {
+        let key_path = Path::new(key.trim_end_matches('/'));
+
+        let exact = self.exact(key);
+        if !exact.is_empty() {
+            return Self::unique(exact);
+        }
+
+        if key_path.is_relative() {
+            let suffix = self.filter(|path| path.ends_with(key_path));
+            if !suffix.is_empty() {
+                return Self::unique(suffix);
+            }
+
+            let key_parts: Vec<_> = key_path.components().collect();
+            let component = self.filter(|path| {
+                let parts: Vec<_> = path.components().collect();
+                !key_parts.is_empty() && parts.windows(key_parts.len()).any(|w| w == key_parts)
+            });
+            if !component.is_empty() {
+                return Self::unique(component);
+            }
+        }
+
+        if key.contains(['*', '?', '[']) {
+            let pattern = match key_path.is_relative() {
+                true => format!("**/{}", key),
+                false => key.to_string(),
+            };
+
+            let options = glob::MatchOptions {
+                require_literal_separator: true,
+                ..Default::default()
+            };
+
+            return match glob::Pattern::new(&pattern) {
+                Ok(pattern) => {
+                    let globbed = self.filter(|path| pattern.matches_path_with(path, options));
+                    match globbed.is_empty() {
+                        true => Match::NotFound,
+                        false => Match::Found(globbed),
+                    }
+                }
+                Err(_) => Match::NotFound,
+            };
+        }
+
+        let mut fuzzy: Vec<(usize, &Path)> = self
+            .paths
+            .iter()
+            .filter_map(|path| fuzzy_score(key, &path.display().to_string()).map(|s| (s, *path)))
+            .collect();
+        fuzzy.sort_by_key(|(score, _)| *score);
+
+        // Drop matches that skip more than twice as many characters as the
+        // best, so a clear winner is found & only near ties are ambiguous.
+        let best = fuzzy.first().map(|(score, _)| *score).unwrap_or_default();
+        Self::unique(
+            fuzzy
+                .into_iter()
+                .filter(|(score, _)| *score <= best * 2)
+                .map(|(_, path)| path)
+                .collect(),
+        )
+    }
+
+    /// Whether `key` is one of the paths, exactly, rather than a suffix, glob
+    /// etc. of them.
+    ///
+    /// * `key`: &str
+    pub fn is_exact(&self, key: &str) -> bool {
+        !self.exact(key).is_empty()
+    }
+
+    /// * `key`: &str
+    fn exact(&self, key: &str) -> Vec<&'a Path> {
+        let key_path = Path::new(key.trim_end_matches('/'));
+        self.filter(|path| path == key_path || path.prepend_home() == key_path.prepend_home())
+    }
+
+    /// Find paths matching each of `keys`. Ambiguous keys are resolved by
+    /// asking the user to pick, if there's a user to ask, otherwise they're an
+    /// error, as is a key that matches nothing.
+    ///
+    /// * `keys`: &[String]
+    pub fn select(&self, keys: &[String]) -> anyhow::Result<Vec<&'a Path>> {
+        let mut selected: Vec<&Path> = Vec::new();
+        for key in keys {
+            let found = match self.find(key) {
+                Match::Found(paths) => paths,
+                Match::Ambiguous(paths) => vec![pick(key, &paths)?],
+                Match::NotFound => return Err(Error::NotFound(key.to_string()).into()),
+            };
+
+            for path in found {
+                if !selected.contains(&path) {
+                    selected.push(path);
+                }
+            }
+        }
+
+        Ok(selected)
+    }
+
+    /// Keys that select `path` & nothing else, from shortest to longest.
+    ///
+    /// * `path`: &Path
+    pub fn keys(&self, path: &Path) -> Vec<String> {
+        let parts: Vec<_> = path.components().collect();
+        (1..=parts.len())
+            .rev()
+            .map(|n| parts[n - 1..].iter().collect::<PathBuf>())
+            .map(|key| key.display().to_string())
+            .filter(|key| self.find(key) == Match::Found(vec![path]))
+            .collect()
+    }
+
+    fn filter(&self, predicate: impl Fn(&Path) -> bool) -> Vec<&'a Path> {
+        self.paths
+            .iter()
+            .copied()
+            .filter(|p| predicate(p))
+            .collect()
+    }
+
+    fn unique(paths: Vec<&'a Path>) -> Match<'a> {
+        match paths.len() {
+            0 => Match::NotFound,
+            1 => Match::Found(paths),
+            _ => Match::Ambiguous(paths),
+        }
+    }
+}
+
+/// If `key` is a case-insensitive subsequence of `text`, score the tightest
+/// match by how many characters of `text` it skips. Lower is better, 0 is a
+/// substring.
+///
+/// * `key`: &str
+/// * `text`: &str
+fn fuzzy_score(key: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let key: Vec<char> = key.to_lowercase().chars().collect();
+    let first = *key.first()?;
+
+    // Greedy from each place the key could start, keeping the shortest span.
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| {
+            let mut end = start;
+            for k in &key[1..] {
+                end += 1 + text.get(end + 1..)?.iter().position(|c| c == k)?;
+            }
+            Some(end - start + 1 - key.len())
+        })
+        .min()
+}
+
+/// Ask the user which of `paths` they meant by `key`, if we're able to ask.
+///
+/// * `key`: &str
+/// * `paths`: &[&Path], candidates.
+fn pick<'a>(key: &str, paths: &[&'a Path]) -> anyhow::Result<&'a Path> {
+    let items: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+
+    if !console::user_attended_stderr() {
+        return Err(Error::Ambiguous {
+            key: key.to_string(),
+            candidates: items.join(", "),
+        }
+        .into());
+    }
+
+    let choice = dialoguer::Select::new()
+        .with_prompt(format!("`{}` is ambiguous, which did you mean?", key))
+        .items(&items)
+        .default(0)
+        .interact()
+        .map_err(|err| Error::Aborted(err.to_string()))?;
+
+    Ok(paths[choice])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Match, Matcher};
+    use std::path::{Path, PathBuf};
+
+    fn sources() -> Vec<PathBuf> {
+        [
+            ".config/bspwm",
+            ".config/nvim/init.lua",
+            ".config/sway/config",
+            ".profile",
+            "/etc/hostname",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+
+    #[test]
+    fn exact_suffix_and_component_matches() {
+        let sources = sources();
+        let matcher = Matcher::new(sources.iter().map(PathBuf::as_path));
+
+        let found = |path: &'static str| Match::Found(vec![Path::new(path)]);
+        assert_eq!(matcher.find("/etc/hostname"), found("/etc/hostname"));
+        assert_eq!(matcher.find("hostname"), found("/etc/hostname"));
+        assert_eq!(matcher.find("nvim"), found(".config/nvim/init.lua"));
+        assert_eq!(matcher.find("sway/config"), found(".config/sway/config"));
+    }
+
+    #[test]
+    fn ambiguous_glob_and_fuzzy_matches() {
+        let sources = sources();
+        let matcher = Matcher::new(sources.iter().map(PathBuf::as_path));
+
+        // `config` is a suffix of one path, so it isn't ambiguous, but
+        // `.config` is a component of three.
+        assert_eq!(
+            matcher.find("config"),
+            Match::Found(vec![Path::new(".config/sway/config")])
+        );
+        assert!(matches!(matcher.find(".config"), Match::Ambiguous(p) if p.len() == 3));
+
+        assert!(matches!(matcher.find(".config/*"), Match::Found(p) if p.len() == 1));
+        assert!(matches!(matcher.find(".config/**/*"), Match::Found(p) if p.len() == 3));
+
+        assert_eq!(
+            matcher.find("bsp"),
+            Match::Found(vec![Path::new(".config/bspwm")])
+        );
+        // A substring beats a scattered match, but equally tight matches
+        // are still ambiguous.
+        assert_eq!(
+            matcher.find("sw"),
+            Match::Found(vec![Path::new(".config/sway/config")])
+        );
+        assert!(matches!(matcher.find("cn"), Match::Ambiguous(p) if p.len() == 3));
+        assert_eq!(matcher.find("zsh"), Match::NotFound);
+    }
+
+    #[test]
+    fn keys_are_unique() {
+        let sources = sources();
+        let matcher = Matcher::new(sources.iter().map(PathBuf::as_path));
+
+        assert_eq!(
+            matcher.keys(Path::new(".config/sway/config")),
+            ["config", "sway/config", ".config/sway/config"]
+        );
+    }
+}
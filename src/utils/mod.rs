@@ -1,27 +1,111 @@
+pub mod ignore;
+pub mod json;
+pub mod matcher;
 pub mod paths;
 pub use paths::join_paths;
 pub mod spinner;
+#[cfg(test)]
+pub mod testing;
+pub mod yaml;
 
 use crate::errors::Error;
-use std::process::Command;
+use std::{ffi::OsStr, process::Command, time::Duration};
 
 pub fn run_cmd(prog: &str, args: &[&str]) -> anyhow::Result<()> {
+    run_cmd_output(prog, args)?;
+    Ok(())
+}
+
+/// Same as `run_cmd`, but `stdout` is captured & returned, trimmed, so that
+/// callers may inspect it.
+pub fn run_cmd_output(prog: &str, args: &[&str]) -> anyhow::Result<String> {
+    run_cmd_env(prog, args, std::iter::empty::<(&str, &str)>())
+}
+
+/// Same as `run_cmd_output`, with additional environment variables.
+///
+/// * `prog`: &str
+/// * `args`: &[&str]
+/// * `env`: variables to set, as (name, value) pairs.
+pub fn run_cmd_env<K, V>(
+    prog: &str,
+    args: &[&str],
+    env: impl IntoIterator<Item = (K, V)>,
+) -> anyhow::Result<String>
+where
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
     log::trace!("`{} {}`...", prog, args.join(" "));
 
-    let output = Command::new(prog).args(args).output()?;
+    let output = Command::new(prog).args(args).envs(env).output()?;
     if !output.status.success() {
-        if !output.stderr.is_empty() {
-            eprintln!("{}", String::from_utf8(output.stderr)?);
+        let stderr = String::from_utf8(output.stderr)?;
+        if !stderr.is_empty() {
+            eprintln!("{}", stderr);
         }
 
         if log::log_enabled!(log::Level::Trace) && !output.stdout.is_empty() {
-            println!("{}", String::from_utf8(output.stdout)?);
+            eprintln!("{}", String::from_utf8(output.stdout)?);
         }
 
-        return Err(Error::Command(format!("`{} {}` failed", prog, args.join(" "))).into());
+        let cmd = format!("{} {}", prog, args.join(" "));
+        let code = output.status.code();
+        return Err(Error::Command { cmd, code, stderr }.into());
     }
 
-    Ok(())
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Whether `prog` is an executable file in one of $PATH.
+///
+/// * `prog`: &str
+pub fn is_on_path(prog: &str) -> bool {
+    is_on(prog, &std::env::var_os("PATH").unwrap_or_default())
+}
+
+/// Same as `is_on_path`, with `paths` in place of $PATH.
+///
+/// * `prog`: &str
+/// * `paths`: &OsStr, e.g. `/usr/local/bin:/usr/bin`.
+pub fn is_on(prog: &str, paths: &OsStr) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::env::split_paths(paths).any(|dir| {
+        dir.join(prog)
+            .metadata()
+            .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    })
+}
+
+/// Name of this machine, e.g. to pick a host profile.
+pub fn hostname() -> anyhow::Result<String> {
+    run_cmd_output("uname", &["-n"])
+}
+
+/// Parse a duration such as `500ms`, `30s`, `10m`, `1h` or `1d`.
+///
+/// * `raw`: &str
+pub fn parse_duration(raw: &str) -> anyhow::Result<Duration> {
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (value, unit) = raw.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("`{}` isn't a duration, e.g. `30s` or `1h`", raw))?;
+
+    let secs = match unit {
+        "ms" => return Ok(Duration::from_millis(value)),
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => anyhow::bail!("Unknown unit `{}` of `{}`, use ms, s, m, h or d", unit, raw),
+    };
+
+    match value.checked_mul(secs) {
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => anyhow::bail!("`{}` is too long a duration", raw),
+    }
 }
 
 pub fn get_timestamp() -> String {
@@ -5,6 +5,7 @@ use std::path::{Path, PathBuf};
 pub trait ResolvePath {
     fn resolve_path(&self) -> anyhow::Result<PathBuf>;
     fn prepend_home(&self) -> PathBuf;
+    fn strip_home(&self) -> PathBuf;
 }
 
 impl ResolvePath for Path {
@@ -19,14 +20,20 @@ impl ResolvePath for Path {
     fn prepend_home(&self) -> PathBuf {
         self.to_path_buf().prepend_home()
     }
+
+    /// See ResolvePath for PathBuf. Here, we convert Path to PathBuf, and run
+    /// the equivalent function.
+    fn strip_home(&self) -> PathBuf {
+        self.to_path_buf().strip_home()
+    }
 }
 
 impl ResolvePath for PathBuf {
     /// Given PathBuf is automatically coerced to a Path as it's passed to this
     /// fn by reference.
     ///
-    /// We check if the path exists, and if it doesn't, we
-    /// prepend $HOME.
+    /// First, `~` & variables are expanded, see `expand`. We check if the
+    /// path exists, and if it doesn't, we prepend $HOME.
     ///
     /// Finally, we canonicalize the path since we don't want to
     /// use a relative path. Failure to canonicalize will result in an error
@@ -34,9 +41,10 @@ impl ResolvePath for PathBuf {
     fn resolve_path(&self) -> anyhow::Result<PathBuf> {
         log::trace!("Resolving `{}`...", self.display());
 
-        let mut path = self.clone();
+        let expanded = PathBuf::from(expand(&self.to_string_lossy())?);
+        let mut path = expanded.clone();
         if !path.exists() {
-            path = self.prepend_home();
+            path = expanded.prepend_home();
         }
 
         match path.canonicalize() {
@@ -62,6 +70,22 @@ impl ResolvePath for PathBuf {
 
         path
     }
+
+    /// The reverse of `prepend_home`, paths outside of $HOME are left as they
+    /// are.
+    fn strip_home(&self) -> PathBuf {
+        let home = BaseDirs::new()
+            .expect("No BaseDirs")
+            .home_dir()
+            .to_path_buf();
+        let path = match self.strip_prefix(&home) {
+            Ok(path) => path.to_path_buf(),
+            Err(_) => self.clone(),
+        };
+        log::trace!("-$HOME `{}` -> `{}`", self.display(), path.display());
+
+        path
+    }
 }
 
 pub trait MakeDirs {
@@ -89,34 +113,9 @@ impl MakeDirs for PathBuf {
     }
 }
 
-pub trait RemoveDirs {
-    fn remove_all_dirs(&self) -> anyhow::Result<()>;
-}
-
-impl RemoveDirs for Path {
-    fn remove_all_dirs(&self) -> anyhow::Result<()> {
-        if let Err(err) = std::fs::remove_dir_all(self) {
-            let err = Error::RemoveDir {
-                path: self.display().to_string(),
-                err,
-            };
-            Err(err.into())
-        } else {
-            log::debug!("{}{}", console::style("--").red(), self.display());
-            Ok(())
-        }
-    }
-}
-
-impl RemoveDirs for PathBuf {
-    fn remove_all_dirs(&self) -> anyhow::Result<()> {
-        self.as_path().remove_all_dirs()
-    }
-}
-
 pub trait Metadata {
     fn get_metadata(&self) -> anyhow::Result<std::fs::Metadata>;
-    fn get_modified(&self) -> anyhow::Result<std::time::SystemTime>;
+    fn get_size(&self) -> anyhow::Result<u64>;
 }
 
 impl Metadata for Path {
@@ -129,13 +128,24 @@ impl Metadata for Path {
         }
     }
 
-    fn get_modified(&self) -> anyhow::Result<std::time::SystemTime> {
-        if let Ok(modified) = self.get_metadata()?.modified() {
-            Ok(modified)
-        } else {
-            let err = Error::ModifiedTime(self.display().to_string());
-            Err(err.into())
+    /// Size in bytes, directories are walked recursively. Symlinks within
+    /// directories aren't followed, so broken ones count as the link itself.
+    fn get_size(&self) -> anyhow::Result<u64> {
+        let metadata = self.get_metadata()?;
+        if !metadata.is_dir() {
+            return Ok(metadata.len());
         }
+
+        let mut size = 0;
+        for entry in self.read_dir()? {
+            let entry = entry?;
+            size += match entry.file_type()?.is_dir() {
+                true => entry.path().get_size()?,
+                false => entry.metadata()?.len(),
+            };
+        }
+
+        Ok(size)
     }
 }
 
@@ -144,11 +154,124 @@ impl Metadata for PathBuf {
         self.as_path().get_metadata()
     }
 
-    fn get_modified(&self) -> anyhow::Result<std::time::SystemTime> {
-        self.as_path().get_modified()
+    fn get_size(&self) -> anyhow::Result<u64> {
+        self.as_path().get_size()
     }
 }
 
+/// XDG base directories, & where they are when unset.
+///
+/// * `var`: &str
+fn xdg_default(var: &str) -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new().expect("No BaseDirs");
+    let path = match var {
+        "XDG_CACHE_HOME" => base_dirs.cache_dir().to_path_buf(),
+        "XDG_CONFIG_HOME" => base_dirs.config_dir().to_path_buf(),
+        "XDG_DATA_HOME" => base_dirs.data_dir().to_path_buf(),
+        "XDG_STATE_HOME" => base_dirs.home_dir().join(".local/state"),
+        _ => return None,
+    };
+
+    Some(path)
+}
+
+/// Value of environment variable `var`, if it's set.
+///
+/// * `var`: &str
+pub fn env_var(var: &str) -> Option<String> {
+    std::env::var(var).ok()
+}
+
+/// Value of variable `var` from `env`, XDG base directories fall back to
+/// their defaults.
+///
+/// * `var`: &str
+/// * `env`: impl Fn(&str) -> Option<String>, e.g. `env_var`.
+fn lookup(var: &str, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<String> {
+    match env(var) {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => match xdg_default(var) {
+            Some(path) => Ok(path.display().to_string()),
+            None => Err(Error::UndefinedVar(var.to_string()).into()),
+        },
+    }
+}
+
+/// If `raw` begins with a variable, e.g. `$XDG_CONFIG_HOME/nvim` or
+/// `${XDG_CONFIG_HOME}/nvim`, split it into the variable's name & the rest.
+///
+/// * `raw`: &str
+pub fn split_var(raw: &str) -> Option<(&str, &str)> {
+    let (name, rest) = match raw.strip_prefix("${") {
+        Some(braced) => braced.split_once('}')?,
+        None => {
+            let unbraced = raw.strip_prefix('$')?;
+            let end = unbraced
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(unbraced.len());
+            unbraced.split_at(end)
+        }
+    };
+
+    match name.is_empty() {
+        true => None,
+        false => Some((name, rest)),
+    }
+}
+
+/// Expand a leading `~`, & `$VAR` or `${VAR}` anywhere in `raw`. Undefined
+/// variables are an error, other than XDG base directories, which fall back
+/// to their defaults, e.g. `$XDG_CONFIG_HOME` is `~/.config`.
+///
+/// * `raw`: &str
+pub fn expand(raw: &str) -> anyhow::Result<String> {
+    expand_with(raw, env_var)
+}
+
+/// See `expand`, with variables looked up in `env` rather than the
+/// environment.
+///
+/// * `raw`: &str
+/// * `env`: impl Fn(&str) -> Option<String>
+pub fn expand_with(raw: &str, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<String> {
+    let mut expanded = String::new();
+    let mut rest = raw;
+
+    if rest == "~" || rest.starts_with("~/") {
+        let home = BaseDirs::new()
+            .expect("No BaseDirs")
+            .home_dir()
+            .to_path_buf();
+        expanded.push_str(&home.display().to_string());
+        rest = &rest[1..];
+    }
+
+    while let Some(i) = rest.find('$') {
+        expanded.push_str(&rest[..i]);
+        match split_var(&rest[i..]) {
+            Some((var, after)) => {
+                expanded.push_str(&lookup(var, &env)?);
+                rest = after;
+            }
+            None => {
+                expanded.push('$');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+/// Where `path` is on this machine: expanded, see `expand`, & relative to
+/// $HOME unless absolute. Unlike `resolve_path`, `path` needn't exist.
+///
+/// * `path`: &Path
+pub fn expand_path(path: &Path) -> anyhow::Result<PathBuf> {
+    Ok(PathBuf::from(expand(&path.to_string_lossy())?).prepend_home())
+}
+
 /// Splits left & right paths by '/' (Linux only) then combines them into
 /// an absolute path.
 ///
@@ -176,7 +299,7 @@ pub fn join_paths(left: &Path, right: &Path) -> PathBuf {
 
 #[cfg(test)]
 mod tests {
-    use super::{MakeDirs, Metadata, RemoveDirs, ResolvePath};
+    use super::{expand_with, split_var, MakeDirs, Metadata, ResolvePath};
     use std::{fs::File, io::Write, path::Path};
 
     #[test]
@@ -199,19 +322,16 @@ mod tests {
     }
 
     #[test]
-    fn make_remove_all_dirs() {
-        let mut path = Path::new("nested/dir/structure/");
+    fn make_all_dirs() {
+        let path = Path::new("nested/dir/structure/");
         assert!(path.make_all_dirs().is_ok());
+        assert!(path.is_dir());
 
-        path = Path::new("nested/");
-        assert!(path.remove_all_dirs().is_ok());
-
-        // Check it's been deleted.
-        assert!(!path.exists())
+        std::fs::remove_dir_all("nested/").expect("failed to remove nested/");
     }
 
     #[test]
-    fn get_metadata_modified() {
+    fn get_metadata() {
         let mut file = File::create("metadata.txt").expect("failed to create metadata.txt");
 
         let path = Path::new("metadata.txt");
@@ -219,9 +339,34 @@ mod tests {
 
         file.write_all(b"Retrieving metadata")
             .expect("failed to write to metadata.txt");
-        assert!(path.get_modified().is_ok());
+        assert!(path.get_size().is_ok_and(|size| size > 0));
 
         std::fs::remove_file("metadata.txt").expect("failed to remove metadata.txt");
         assert!(!path.exists());
     }
+
+    #[test]
+    fn expand_vars_and_tilde() {
+        let env = |var: &str| (var == "NEDOTS_TEST_VAR").then(|| "value".to_string());
+        let expand = |raw| expand_with(raw, env);
+        let home = directories::BaseDirs::new()
+            .unwrap()
+            .home_dir()
+            .to_path_buf();
+
+        assert_eq!(
+            expand("~/.zshrc").unwrap(),
+            format!("{}/.zshrc", home.display())
+        );
+        assert_eq!(expand("a/$NEDOTS_TEST_VAR/b").unwrap(), "a/value/b");
+        assert_eq!(expand("${NEDOTS_TEST_VAR}b").unwrap(), "valueb");
+        assert_eq!(expand("no~vars$").unwrap(), "no~vars$");
+        assert!(expand("$NEDOTS_UNDEFINED_VAR/x").is_err());
+
+        assert_eq!(
+            split_var("${XDG_CONFIG_HOME}/nvim"),
+            Some(("XDG_CONFIG_HOME", "/nvim"))
+        );
+        assert_eq!(split_var(".config/nvim"), None);
+    }
 }
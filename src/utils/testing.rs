@@ -0,0 +1,107 @@
+//! Fixtures shared by tests, e.g. local git repositories to clone & push to.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Run `git -C path args`, failing the test if it fails.
+///
+/// * `path`: &Path
+/// * `args`: &[&str]
+pub fn git(path: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .status()
+        .expect("failed to run git");
+    assert!(status.success(), "`git {}` failed", args.join(" "));
+}
+
+/// `git init` a repository @ `path`, with an identity to commit as.
+///
+/// * `path`: &Path
+pub fn init_repo(path: &Path) {
+    std::fs::create_dir_all(path).expect("failed to create repository");
+    git(path, &["init", "--quiet", "--initial-branch=main"]);
+    git(path, &["config", "user.name", "nedots"]);
+    git(path, &["config", "user.email", "nedots@localhost"]);
+}
+
+/// Write each of `files`, relative to `repo`, & commit them.
+///
+/// * `repo`: &Path
+/// * `files`: &[(&str, &str)], as (path, contents) pairs.
+pub fn commit_files(repo: &Path, files: &[(&str, &str)]) {
+    for (path, contents) in files {
+        let path = repo.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("failed to create dir");
+        std::fs::write(path, contents).expect("failed to write file");
+    }
+
+    git(repo, &["add", "--all"]);
+    git(repo, &["commit", "--quiet", "--allow-empty", "-m", "Test"]);
+}
+
+/// A bare repository @ `dir/{name}.git` with `files` committed, to clone from.
+///
+/// * `dir`: &Path
+/// * `name`: &str
+/// * `files`: &[(&str, &str)], as (path, contents) pairs.
+pub fn bare_repo(dir: &Path, name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let work = dir.join(format!("{}-work", name));
+    init_repo(&work);
+    commit_files(&work, files);
+
+    let bare = dir.join(format!("{}.git", name));
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--bare"])
+        .arg(&work)
+        .arg(&bare)
+        .status()
+        .expect("failed to run git");
+    assert!(status.success());
+    bare
+}
+
+/// Register `remote` as a submodule of `root` @ `path`, & commit it.
+///
+/// * `root`: &Path
+/// * `remote`: &Path
+/// * `path`: &Path, relative to `root`.
+pub fn add_submodule(root: &Path, remote: &Path, path: &Path) {
+    // Submodules from local paths are refused by default.
+    let (remote, path) = (remote.display().to_string(), path.display().to_string());
+    let args = [
+        "-c",
+        "protocol.file.allow=always",
+        "submodule",
+        "add",
+        "--quiet",
+    ];
+    git(root, &[&args[..], &[&remote, &path]].concat());
+    git(root, &["commit", "--quiet", "-m", "Add submodule"]);
+}
+
+/// Output of `git -C path args`, trimmed.
+///
+/// * `path`: &Path
+/// * `args`: &[&str]
+pub fn git_output(path: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()
+        .expect("failed to run git");
+    assert!(output.status.success(), "`git {}` failed", args.join(" "));
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// `HEAD` of the repository @ `path`.
+///
+/// * `path`: &Path
+pub fn head(path: &Path) -> String {
+    git_output(path, &["rev-parse", "HEAD"])
+}
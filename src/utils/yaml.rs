@@ -0,0 +1,350 @@
+//! Edit top-level lists in YAML as text, so that comments & ordering survive.
+//! Round-tripping through `serde_yaml` would lose both.
+
+use std::ops::Range;
+
+/// An item of a top-level block sequence.
+#[derive(Debug)]
+struct ListItem {
+    /// Lines that the item spans.
+    lines: Range<usize>,
+    value: serde_yaml::Value,
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    line == "-" || line.starts_with("- ")
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Find top-level `key`, returning the line it's on & the line its block ends.
+/// Trailing blank lines & comments are left to whatever comes next.
+///
+/// * `lines`: &[String]
+/// * `key`: &str
+fn find_block(lines: &[String], key: &str) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|line| {
+        line.strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    })?;
+
+    let mut end = start + 1;
+    while end < lines.len()
+        && (is_blank_or_comment(&lines[end])
+            || lines[end].starts_with([' ', '\t'])
+            || is_list_item(&lines[end]))
+    {
+        end += 1;
+    }
+
+    while end > start + 1 && is_blank_or_comment(&lines[end - 1]) {
+        end -= 1;
+    }
+
+    Some((start, end))
+}
+
+/// If `key` has an inline value, e.g. `sources: []`, rewrite it as a block
+/// sequence so that items can be added & removed line by line.
+///
+/// * `lines`: &mut Vec<String>
+/// * `key`: &str
+fn expand_inline(lines: &mut Vec<String>, key: &str) -> anyhow::Result<()> {
+    let Some((start, _)) = find_block(lines, key) else {
+        return Ok(());
+    };
+
+    let inline = lines[start][key.len()..].trim_start()[1..].trim();
+    if inline.is_empty() || inline.starts_with('#') {
+        return Ok(());
+    }
+
+    let items: Vec<serde_yaml::Value> = serde_yaml::from_str(inline)?;
+    let mut block = vec![format!("{}:", key)];
+    for item in &items {
+        block.extend(render_item(item, 2)?);
+    }
+
+    lines.splice(start..start + 1, block);
+    Ok(())
+}
+
+/// Render `value` as an item of a block sequence, indented by `indent`.
+///
+/// * `value`: &serde_yaml::Value
+/// * `indent`: usize
+pub fn render_item(value: &serde_yaml::Value, indent: usize) -> anyhow::Result<Vec<String>> {
+    let pad = " ".repeat(indent);
+    Ok(serde_yaml::to_string(value)?
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => format!("{}- {}", pad, line),
+            _ => format!("{}  {}", pad, line),
+        })
+        .collect())
+}
+
+/// Items of the block sequence in `lines[start..end]`, each parsed as YAML.
+///
+/// * `lines`: &[String]
+/// * `block`: (usize, usize), as returned by `find_block`.
+fn list_items(lines: &[String], (start, end): (usize, usize)) -> anyhow::Result<Vec<ListItem>> {
+    let Some(indent) = lines[start + 1..end]
+        .iter()
+        .find(|line| is_list_item(line))
+        .map(|line| indent_of(line))
+    else {
+        return Ok(Vec::new());
+    };
+
+    let starts: Vec<usize> = (start + 1..end)
+        .filter(|&i| is_list_item(&lines[i]) && indent_of(&lines[i]) == indent)
+        .collect();
+
+    let mut items = Vec::new();
+    for (n, &item_start) in starts.iter().enumerate() {
+        let mut item_end = starts.get(n + 1).copied().unwrap_or(end);
+        while item_end > item_start + 1 && is_blank_or_comment(&lines[item_end - 1]) {
+            item_end -= 1;
+        }
+
+        let raw: Vec<&str> = lines[item_start..item_end]
+            .iter()
+            .map(|line| line.get(indent..).unwrap_or_default())
+            .collect();
+        let mut seq: Vec<serde_yaml::Value> = serde_yaml::from_str(&raw.join("\n"))?;
+
+        items.push(ListItem {
+            lines: item_start..item_end,
+            value: seq.pop().unwrap_or_default(),
+        });
+    }
+
+    Ok(items)
+}
+
+fn split_lines(raw: &str) -> Vec<String> {
+    raw.lines().map(str::to_string).collect()
+}
+
+fn join_lines(lines: &[String]) -> String {
+    let mut raw = lines.join("\n");
+    raw.push('\n');
+    raw
+}
+
+/// Append `value` to the top-level list `key`, creating `key` if it doesn't
+/// exist.
+///
+/// * `raw`: &str, YAML.
+/// * `key`: &str
+/// * `value`: &serde_yaml::Value
+pub fn append_to_list(raw: &str, key: &str, value: &serde_yaml::Value) -> anyhow::Result<String> {
+    let mut lines = split_lines(raw);
+    expand_inline(&mut lines, key)?;
+
+    match find_block(&lines, key) {
+        Some(block) => {
+            let indent = match list_items(&lines, block)?.first() {
+                Some(item) => indent_of(&lines[item.lines.start]),
+                None => 2,
+            };
+            lines.splice(block.1..block.1, render_item(value, indent)?);
+        }
+        None => {
+            lines.push(format!("{}:", key));
+            lines.extend(render_item(value, 2)?);
+        }
+    }
+
+    Ok(join_lines(&lines))
+}
+
+/// Remove items of top-level list `key` that satisfy `predicate`. Returns the
+/// edited YAML & the number of items removed.
+///
+/// * `raw`: &str, YAML.
+/// * `key`: &str
+/// * `predicate`: called with each item of the list.
+pub fn remove_from_list(
+    raw: &str,
+    key: &str,
+    predicate: impl Fn(&serde_yaml::Value) -> bool,
+) -> anyhow::Result<(String, usize)> {
+    let mut lines = split_lines(raw);
+    expand_inline(&mut lines, key)?;
+
+    let Some(block) = find_block(&lines, key) else {
+        return Ok((raw.to_string(), 0));
+    };
+
+    let remove: Vec<ListItem> = list_items(&lines, block)?
+        .into_iter()
+        .filter(|item| predicate(&item.value))
+        .collect();
+
+    // Back to front, so that earlier ranges remain valid.
+    for item in remove.iter().rev() {
+        lines.drain(item.lines.clone());
+    }
+
+    // An empty block would be `null`, rather than an empty list.
+    if let Some(block) = find_block(&lines, key) {
+        if list_items(&lines, block)?.is_empty() {
+            lines[block.0] = format!("{}: []", key);
+        }
+    }
+
+    Ok((join_lines(&lines), remove.len()))
+}
+
+/// Find the line & column, both starting at 1, of the value @ `path`, e.g.
+/// `["git_repos", "0", "path"]`. Numeric segments index into block sequences.
+/// If `path` can only be partially followed, e.g. into an inline list, the
+/// deepest location found is returned.
+///
+/// * `raw`: &str, YAML.
+/// * `path`: &[&str]
+pub fn locate(raw: &str, path: &[&str]) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = raw.lines().collect();
+    let mut found = None;
+    // Lines of the block that the next segment is looked for in.
+    let mut block = 0..lines.len();
+
+    for (depth, segment) in path.iter().enumerate() {
+        let next = match segment.parse::<usize>() {
+            Ok(index) => {
+                let indent = lines[block.clone()]
+                    .iter()
+                    .find(|line| is_list_item(line))
+                    .map(|line| indent_of(line));
+
+                lines
+                    .iter()
+                    .enumerate()
+                    .take(block.end)
+                    .skip(block.start)
+                    .filter(|(_, line)| is_list_item(line) && Some(indent_of(line)) == indent)
+                    .nth(index)
+                    .map(|(i, line)| {
+                        let indent = indent_of(line);
+                        (i, indent + 2, block_end(&lines, i, indent, false))
+                    })
+            }
+            Err(_) => lines
+                .iter()
+                .enumerate()
+                .take(block.end)
+                .skip(block.start)
+                .find_map(|(i, line)| {
+                    let column = indent_of(line);
+                    let rest = line[column..].strip_prefix("- ").unwrap_or(&line[column..]);
+                    let column = line.len() - rest.len();
+
+                    let is_key = rest
+                        .strip_prefix(segment)
+                        .is_some_and(|rest| rest.trim_start().starts_with(':'));
+                    (is_key && (depth > 0 || column == 0))
+                        .then(|| (i, column, block_end(&lines, i, column, true)))
+                }),
+        };
+
+        let Some((line, column, end)) = next else {
+            break;
+        };
+
+        found = Some((line + 1, column + 1));
+        // The next segment may be on the same line, e.g. `- path: x`.
+        block = line..end;
+    }
+
+    found
+}
+
+/// The line after the block of the key or sequence item @ `start`, which is
+/// the first line indented no more than `column`. A key's sequence may be
+/// indented as much as the key itself, if `items_at_column`.
+///
+/// * `lines`: &[&str]
+/// * `start`: usize
+/// * `column`: usize, of the key or of the item's `-`.
+/// * `items_at_column`: bool
+fn block_end(lines: &[&str], start: usize, column: usize, items_at_column: bool) -> usize {
+    (start + 1..lines.len())
+        .find(|&i| {
+            let line = lines[i];
+            let indent = indent_of(line);
+            !is_blank_or_comment(line)
+                && (indent < column
+                    || (indent == column && !(items_at_column && is_list_item(line))))
+        })
+        .unwrap_or(lines.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_to_list, locate, remove_from_list};
+
+    const RAW: &str = "# My dots
+remote: git@git.sr.ht:~nedia/nedots
+sources:
+  # Shell
+  - .bashrc
+  - .profile
+
+# Editors
+git_repos:
+  - remote: git@git.sr.ht:~nedia/config.nvim
+    path: .config/nvim
+";
+
+    #[test]
+    fn append_preserves_comments() {
+        let raw = append_to_list(RAW, "sources", &".zshrc".into()).unwrap();
+        assert!(raw.contains("  - .profile\n  - .zshrc\n\n# Editors\n"));
+        assert!(raw.starts_with("# My dots\n"));
+    }
+
+    #[test]
+    fn append_to_inline_and_missing_lists() {
+        let raw = append_to_list("sources: []\n", "sources", &".zshrc".into()).unwrap();
+        assert_eq!(raw, "sources:\n  - .zshrc\n");
+
+        let raw = append_to_list("remote: x\n", "sources", &".zshrc".into()).unwrap();
+        assert_eq!(raw, "remote: x\nsources:\n  - .zshrc\n");
+    }
+
+    #[test]
+    fn remove_mapping_item() {
+        let (raw, removed) = remove_from_list(RAW, "git_repos", |value| {
+            value.get("path").and_then(|p| p.as_str()) == Some(".config/nvim")
+        })
+        .unwrap();
+
+        assert_eq!(removed, 1);
+        assert!(raw.ends_with("# Editors\ngit_repos: []\n"));
+        assert!(raw.contains("  # Shell\n  - .bashrc\n"));
+    }
+
+    #[test]
+    fn locate_keys_and_items() {
+        assert_eq!(locate(RAW, &["remote"]), Some((2, 1)));
+        assert_eq!(locate(RAW, &["sources", "1"]), Some((6, 5)));
+        assert_eq!(locate(RAW, &["git_repos", "0", "path"]), Some((11, 5)));
+        assert_eq!(locate("sources: [a, b]\n", &["sources", "1"]), Some((1, 1)));
+        assert_eq!(locate(RAW, &["submodules"]), None);
+
+        // An index past the end of `sources` isn't an item of `git_repos`.
+        assert_eq!(locate(RAW, &["sources", "2"]), Some((3, 1)));
+        assert_eq!(locate(RAW, &["git_repos", "0", "depth"]), Some((10, 5)));
+    }
+}
@@ -0,0 +1,67 @@
+use nedots::{Action, Config, Nedots, SourceState};
+
+#[test]
+fn gather_install_backup_restore() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let src = dir.join("home/.config/app");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(src.join("app.conf"), "theme = dark").unwrap();
+
+    let nedots = Nedots::new(Config {
+        root: dir.join("root"),
+        sources: vec![src.clone(), dir.join("home/.missing")],
+        ..Default::default()
+    })
+    .unwrap();
+    let state = |nedots: &Nedots| -> Vec<SourceState> {
+        let status = nedots.status().unwrap();
+        assert!(status.root.is_none());
+        status.sources.into_iter().map(|s| s.state).collect()
+    };
+
+    assert_eq!(
+        state(&nedots),
+        [SourceState::Ungathered, SourceState::Missing]
+    );
+
+    let report = nedots.gather().unwrap();
+    assert!(matches!(
+        report.actions[..],
+        [Action::Copied { .. }, Action::Skipped { .. }]
+    ));
+    assert_eq!(
+        state(&nedots),
+        [SourceState::Unchanged, SourceState::Missing]
+    );
+
+    std::fs::write(src.join("app.conf"), "theme = light").unwrap();
+    assert_eq!(
+        state(&nedots),
+        [SourceState::Modified, SourceState::Missing]
+    );
+
+    // Install what was gathered, after backing up what's there.
+    let report = nedots.backup().unwrap();
+    assert!(matches!(
+        report.actions[..],
+        [Action::Copied { .. }, Action::BackedUp { .. }]
+    ));
+    assert!(nedots.install().unwrap().is_changed());
+    let conf = std::fs::read_to_string(src.join("app.conf")).unwrap();
+    assert_eq!(conf, "theme = dark");
+
+    assert_eq!(nedots.backups().unwrap().len(), 1);
+    assert!(nedots.restore(None).unwrap().is_changed());
+    let conf = std::fs::read_to_string(src.join("app.conf")).unwrap();
+    assert_eq!(conf, "theme = light");
+
+    // Nothing exists to back up.
+    let missing = Nedots::new(Config {
+        root: dir.join("root"),
+        sources: vec![dir.join("home/.missing")],
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(!missing.backup().unwrap().is_changed());
+}